@@ -0,0 +1,99 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// A run of frames in the sheet's texture atlas
+#[derive(Clone, Debug, Deserialize)]
+pub struct AnimationClip {
+    pub first: usize,
+    pub last: usize,
+    pub fps: f32,
+    pub looping: bool,
+}
+
+impl AnimationClip {
+    pub fn frame_count(&self) -> usize {
+        self.last.saturating_sub(self.first) + 1
+    }
+}
+
+/// The texture atlas layout and named clips of an animated sprite, loaded from `.anim.ron` files
+#[derive(Clone, Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "0eb8ce9d-a2e2-4a7d-937a-0bf318a95a43"]
+pub struct AnimationSheet {
+    pub texture: String,
+    pub tile_size: (f32, f32),
+    pub columns: usize,
+    pub rows: usize,
+    pub clips: HashMap<String, AnimationClip>,
+}
+
+impl AnimationSheet {
+    /// Catches clips that would break the animator, since they still parse fine
+    pub fn validate(&self) -> Result<(), AnimationSheetError> {
+        let frame_count = self.columns * self.rows;
+
+        for (name, clip) in &self.clips {
+            if !clip.fps.is_finite() || clip.fps <= 0. {
+                return Err(AnimationSheetError::InvalidFps { clip: name.clone(), fps: clip.fps });
+            }
+            if clip.first > clip.last || clip.last >= frame_count {
+                return Err(AnimationSheetError::InvalidFrames { clip: name.clone(), frame_count });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn texture_atlas(&self, texture: Handle<Image>) -> TextureAtlas {
+        TextureAtlas::from_grid(texture, Vec2::new(self.tile_size.0, self.tile_size.1), self.columns, self.rows, None, None)
+    }
+}
+
+#[derive(Debug)]
+pub enum AnimationSheetError {
+    /// Frame times are `1 / fps`, so it has to be positive and finite
+    InvalidFps { clip: String, fps: f32 },
+    /// Frames out of order or past the end of the atlas
+    InvalidFrames { clip: String, frame_count: usize },
+}
+
+impl fmt::Display for AnimationSheetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnimationSheetError::InvalidFps { clip, fps } => write!(f, "clip \"{}\" has an fps of {}, it has to be positive", clip, fps),
+            AnimationSheetError::InvalidFrames { clip, frame_count } => {
+                write!(f, "clip \"{}\" has frames out of order or past the {} frames of the atlas", clip, frame_count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnimationSheetError {}
+
+#[derive(Default)]
+pub struct AnimationSheetLoader;
+
+impl AssetLoader for AnimationSheetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let sheet: AnimationSheet = ron::de::from_bytes(bytes)?;
+            sheet.validate()?;
+            load_context.set_default_asset(LoadedAsset::new(sheet));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
+    }
+}
@@ -2,6 +2,13 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
+use crate::animation::AnimationSheet;
+use crate::cutscene::CutsceneScript;
+use crate::dialogue::DialogueScript;
+use crate::nav_graph::NavLink;
+use crate::resources::Ability;
+use crate::settings::InputAction;
+
 use std::collections::HashSet;
 
 
@@ -24,10 +31,95 @@ pub struct CameraCoords {
 #[derive(Bundle, Default)]
 pub struct PrimaryCameraBundle {
     pub camera_2d: Camera2dBundle,
-    pub primary_camera: PrimaryCamera
+    pub primary_camera: PrimaryCamera,
+    pub camera_controller: CameraController,
+    pub camera_handoff: CameraHandoff,
+}
+
+/// How the camera follows the player.
+/// The player can move around inside the deadzone without the camera moving,
+/// and vertically the camera only recenters once the player lands
+#[derive(Component)]
+pub struct CameraController {
+    pub offset: Vec2,
+    pub deadzone: Vec2,
+    pub smoothing: f32,
+    pub scale: f32,
+
+    pub look_ahead_distance: f32,
+    pub look_ahead_smoothing: f32,
+
+    pub look_down_distance: f32,
+    pub look_down_fall_speed: f32,
+    pub look_down_smoothing: f32,
+
+    pub is_following: bool,
+    pub focus: Vec2,
+    pub position: Vec2,
+    pub look_ahead: f32,
+    pub look_down: f32,
+    pub camera_zone: Option<Entity>,
+    /// Set by cutscenes to look somewhere other than at the player
+    pub cutscene_focus: Option<Vec2>,
 }
 
+impl Default for CameraController {
+    fn default() -> Self {
+        CameraController {
+            offset: Vec2::new(0., 50.),
+            //half extents
+            deadzone: Vec2::new(24., 48.),
+            smoothing: 6.,
+            scale: 1.5,
+
+            look_ahead_distance: 48.,
+            look_ahead_smoothing: 2.,
 
+            look_down_distance: 96.,
+            look_down_fall_speed: 400.,
+            look_down_smoothing: 3.,
+
+            is_following: false,
+            focus: Vec2::ZERO,
+            position: Vec2::ZERO,
+            look_ahead: 0.,
+            look_down: 0.,
+            camera_zone: None,
+            cutscene_focus: None,
+        }
+    }
+}
+
+/// Blends the camera over to the next level's bounds or camera zone when either changes,
+/// instead of snapping to them
+#[derive(Component)]
+pub struct CameraHandoff {
+    pub level_iid: Option<String>,
+    pub from: Vec2,
+    pub from_scale: f32,
+    pub handoff_seconds: f32,
+    pub timer: Timer,
+}
+
+impl CameraHandoff {
+    pub fn start(&mut self, from: Vec2, from_scale: f32, seconds: f32) {
+        self.from = from;
+        self.from_scale = from_scale;
+        self.timer = Timer::from_seconds(seconds, TimerMode::Once);
+    }
+}
+
+impl Default for CameraHandoff {
+    fn default() -> Self {
+        CameraHandoff {
+            level_iid: None,
+            from: Vec2::ZERO,
+            from_scale: 1.5,
+            handoff_seconds: 0.5,
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+        }
+    }
+}
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Player;
@@ -60,6 +152,16 @@ impl From<&EntityInstance> for ColliderBundle {
                 ccd: Ccd::enabled(),
                 ..default()
             },
+            "Pawn" | "Enemy" => ColliderBundle {
+                collider: Collider::cuboid(entity_instance.width as f32 / 2., entity_instance.height as f32 / 2.),
+                rigid_body: RigidBody::Dynamic,
+                friction: Friction {
+                    coefficient: 0.0,
+                    combine_rule: CoefficientCombineRule::Min,
+                },
+                rotation_constraints,
+                ..default()
+            },
             _ => ColliderBundle::default(),
         }
     }
@@ -67,8 +169,8 @@ impl From<&EntityInstance> for ColliderBundle {
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
 pub struct PlayerBundle {
-    #[sprite_bundle("player.png")]
-    pub sprite_bundle: SpriteBundle,
+    pub sprite_sheet_bundle: SpriteSheetBundle,
+    pub animator: Animator,
     #[from_entity_instance]
     pub collider_bundle: ColliderBundle,
     pub player: Player,
@@ -77,6 +179,8 @@ pub struct PlayerBundle {
     pub movement_bundle: MovementBundle,
     pub ground_detection: GroundDetection,
     pub wall_detection: WallDetection,
+    pub health: Health,
+    pub attacker: Attacker,
 
     // The whole EntityInstance can be stored directly as an EntityInstance component
     #[from_entity_instance]
@@ -118,19 +222,8 @@ pub struct HorizontalMover {
     pub is_horizontal_moving: bool,
 
     pub walk_speed: f32,
-    pub walk_acc: f32,
-    pub walk_dec: f32,
-    pub walk_turn: f32,
-
     pub run_speed: f32,
-    pub run_acc: f32,
-    pub run_dec: f32,
-    pub run_turn: f32,
-
     pub air_speed: f32,
-    pub air_acc: f32,
-    pub air_dec: f32,
-    pub air_turn: f32,
 
     pub current_speed: f32,
 
@@ -149,19 +242,8 @@ impl Default for HorizontalMover {
             is_horizontal_moving: false,
 
             walk_speed: 10000.,
-            walk_acc: 1000.0,
-            walk_dec: 1000.0,
-            walk_turn: 1000.0,
-
             run_speed: 15000.,
-            run_acc: 500.0,
-            run_dec: 1000.0,
-            run_turn: 800.0,
-
             air_speed: 10000.,
-            air_acc: 1000.0,
-            air_dec: 1000.0,
-            air_turn: 1000.0,
 
             current_speed: 0.0,
 
@@ -184,8 +266,10 @@ pub struct VerticalMover {
 
     pub jump_count: i32,
     pub max_jump_count: i32,
+    pub was_on_ground: bool,
 
     pub is_wall_sliding: bool,
+    pub was_wall_sliding: bool,
     pub wall_slide_speed: f32,
     pub in_wall_slide_coyote_time: bool,
     pub wall_slide_coyote_timer: Timer,
@@ -216,8 +300,10 @@ impl Default for VerticalMover {
 
             jump_count: JUMPS,
             max_jump_count: JUMPS,
+            was_on_ground: false,
 
             is_wall_sliding: false,
+            was_wall_sliding: false,
             wall_slide_speed: 10.,
             in_wall_slide_coyote_time: false,
             wall_slide_coyote_timer: Timer::from_seconds(0.2, TimerMode::Once),
@@ -234,6 +320,18 @@ impl Default for VerticalMover {
     }
 }
 
+impl VerticalMover {
+    /// Gravity pulling on the way up a jump, in pixels per second squared
+    pub fn jump_gravity(&self) -> f32 {
+        2. * self.jump_height / (self.time_to_jump_apex * self.time_to_jump_apex)
+    }
+
+    /// Speed a jump starts with, which is scaled by the frame time like the rest of the movement
+    pub fn jump_velocity(&self, delta_seconds: f32) -> f32 {
+        2. * self.jump_height / self.time_to_jump_apex * delta_seconds
+    }
+}
+
 #[derive(Clone, Default, Component)]
 pub struct GroundDetection {
     pub on_ground: bool,
@@ -254,4 +352,1006 @@ pub struct WallDetection {
 pub struct WallSensor {
     pub wall_detection_entity: Entity,
     pub intersecting_wall_entities: HashSet<Entity>,
-}
\ No newline at end of file
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Enemy;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub enum ChessPiece {
+    #[default]
+    Pawn,
+    Rook,
+    Knight,
+    Bishop,
+    Queen,
+    King,
+}
+
+impl ChessPiece {
+    /// Matches the values of the `Chess_piece` enum in the LDtk project
+    pub fn from_identifier(identifier: &str) -> Option<ChessPiece> {
+        match identifier {
+            "Pawn" => Some(ChessPiece::Pawn),
+            "Rook" => Some(ChessPiece::Rook),
+            "Knight" => Some(ChessPiece::Knight),
+            "Bishop" => Some(ChessPiece::Bishop),
+            "Queen" => Some(ChessPiece::Queen),
+            "King" => Some(ChessPiece::King),
+            _ => None,
+        }
+    }
+
+    /// Sprite size of the piece, taller pieces are stronger pieces
+    pub fn size(&self) -> Vec2 {
+        match self {
+            ChessPiece::Pawn => Vec2::new(16., 16.),
+            ChessPiece::Rook | ChessPiece::Knight | ChessPiece::Bishop => Vec2::new(16., 24.),
+            ChessPiece::Queen | ChessPiece::King => Vec2::new(16., 32.),
+        }
+    }
+
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            ChessPiece::Pawn => "Pawn",
+            ChessPiece::Rook => "Rook",
+            ChessPiece::Knight => "Knight",
+            ChessPiece::Bishop => "Bishop",
+            ChessPiece::Queen => "Queen",
+            ChessPiece::King => "King",
+        }
+    }
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct PawnBundle {
+    #[with(placeholder_sprite)]
+    pub sprite_bundle: SpriteBundle,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+    pub enemy: Enemy,
+    pub chess_piece: ChessPiece,
+    pub ground_detection: GroundDetection,
+    #[from_entity_instance]
+    pub promotable: Promotable,
+    #[from_entity_instance]
+    pub health: Health,
+
+    #[from_entity_instance]
+    entity_instance: EntityInstance,
+}
+
+/// Any other piece of the Nightmare Court,
+/// its [`Behaviour`] is looked up from the piece once it spawns
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct EnemyBundle {
+    #[with(placeholder_sprite)]
+    pub sprite_bundle: SpriteBundle,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+    pub enemy: Enemy,
+    #[from_entity_instance]
+    pub chess_piece: ChessPiece,
+    #[from_entity_instance]
+    pub health: Health,
+    pub ground_detection: GroundDetection,
+
+    #[from_entity_instance]
+    entity_instance: EntityInstance,
+}
+
+/// Entities without any art yet are drawn as
+/// blocks in the color they were given in LDtk
+pub fn placeholder_sprite(entity_instance: &EntityInstance) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: entity_instance.smart_color,
+            custom_size: Some(Vec2::new(entity_instance.width as f32, entity_instance.height as f32)),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+#[derive(Clone, Component)]
+pub struct Promotable {
+    pub promote_to: ChessPiece,
+}
+
+impl Default for Promotable {
+    fn default() -> Self {
+        Promotable {
+            promote_to: ChessPiece::Queen,
+        }
+    }
+}
+
+impl From<&EntityInstance> for Promotable {
+    fn from(entity_instance: &EntityInstance) -> Promotable {
+        let promote_to = match entity_instance.get_maybe_enum_field("Promotion") {
+            Ok(Some(piece)) => ChessPiece::from_identifier(piece).unwrap_or(ChessPiece::Queen),
+            _ => ChessPiece::Queen,
+        };
+
+        Promotable { promote_to }
+    }
+}
+
+/// Added to a pawn while it is transforming into its promoted piece
+#[derive(Clone, Component)]
+pub struct Promoting {
+    pub promote_to: ChessPiece,
+    pub timer: Timer,
+}
+
+impl Promoting {
+    pub fn new(promote_to: ChessPiece) -> Promoting {
+        Promoting {
+            promote_to,
+            timer: Timer::from_seconds(1.0, TimerMode::Once),
+        }
+    }
+}
+
+impl From<&EntityInstance> for ChessPiece {
+    fn from(entity_instance: &EntityInstance) -> ChessPiece {
+        match entity_instance.get_maybe_enum_field("Piece") {
+            Ok(Some(piece)) => ChessPiece::from_identifier(piece).unwrap_or_default(),
+            _ => ChessPiece::Pawn,
+        }
+    }
+}
+
+/// Marks the tiles of a level's promotion row,
+/// pawns that reach one of these tiles get promoted
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct PromotionTile;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct PromotionTileBundle {
+    promotion_tile: PromotionTile,
+}
+
+#[derive(Clone, Component)]
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+
+    pub is_invulnerable: bool,
+    pub invulnerability_timer: Timer,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health {
+            current: 5,
+            max: 5,
+
+            is_invulnerable: false,
+            invulnerability_timer: Timer::from_seconds(1.0, TimerMode::Once),
+        }
+    }
+}
+
+/// Enemies only get a short invulnerability window so they can be hit in quick succession
+impl From<&EntityInstance> for Health {
+    fn from(entity_instance: &EntityInstance) -> Health {
+        let current = match entity_instance.get_maybe_int_field("Health") {
+            Ok(Some(health)) => *health,
+            _ => 3,
+        };
+
+        Health {
+            current,
+            max: current,
+            invulnerability_timer: Timer::from_seconds(0.2, TimerMode::Once),
+            ..default()
+        }
+    }
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct ChessBoardBundle {
+    #[from_entity_instance]
+    pub chess_board: ChessBoard,
+    pub threat_map: ThreatMap,
+}
+
+/// A region of the level where chess pieces play by the rules,
+/// moving one square at a time whenever the tick timer finishes
+#[derive(Clone, Component)]
+pub struct ChessBoard {
+    pub columns: i32,
+    pub rows: i32,
+    pub square_size: f32,
+    pub tick_timer: Timer,
+    pub damage: i32,
+}
+
+impl Default for ChessBoard {
+    fn default() -> Self {
+        ChessBoard {
+            columns: 8,
+            rows: 8,
+            square_size: 16.,
+            tick_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            damage: 1,
+        }
+    }
+}
+
+impl From<&EntityInstance> for ChessBoard {
+    fn from(entity_instance: &EntityInstance) -> ChessBoard {
+        let default = ChessBoard::default();
+
+        let square_size = match entity_instance.get_maybe_int_field("Square_size") {
+            Ok(Some(size)) if *size > 0 => *size as f32,
+            _ => default.square_size,
+        };
+        let tick_seconds = match entity_instance.get_maybe_float_field("Tick_seconds") {
+            Ok(Some(seconds)) if *seconds > 0. => *seconds,
+            _ => default.tick_timer.duration().as_secs_f32(),
+        };
+
+        ChessBoard {
+            columns: (entity_instance.width as f32 / square_size) as i32,
+            rows: (entity_instance.height as f32 / square_size) as i32,
+            square_size,
+            tick_timer: Timer::from_seconds(tick_seconds, TimerMode::Repeating),
+            ..default
+        }
+    }
+}
+
+impl ChessBoard {
+    pub fn contains(&self, square: GridCoords) -> bool {
+        square.x >= 0 && square.x < self.columns && square.y >= 0 && square.y < self.rows
+    }
+
+    /// Board square under the given translation, if it is on the board.
+    /// Both translations must be in the same space.
+    pub fn square_at(&self, board_translation: Vec2, translation: Vec2) -> Option<GridCoords> {
+        let half_extents = Vec2::new(self.columns as f32, self.rows as f32) * self.square_size / 2.;
+        let local = ((translation - board_translation + half_extents) / self.square_size).floor();
+        let square = GridCoords::new(local.x as i32, local.y as i32);
+
+        if self.contains(square) { Some(square) } else { None }
+    }
+
+    /// Center of a board square, in the same space as the board translation
+    pub fn square_center(&self, board_translation: Vec2, square: GridCoords) -> Vec2 {
+        let half_extents = Vec2::new(self.columns as f32, self.rows as f32) * self.square_size / 2.;
+        board_translation - half_extents + (Vec2::new(square.x as f32, square.y as f32) + 0.5) * self.square_size
+    }
+}
+
+/// Every square of a board attacked by one of its pieces
+#[derive(Clone, Default, Component)]
+pub struct ThreatMap {
+    pub threatened: HashSet<GridCoords>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct ThreatOverlay;
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct BoardPieceBundle {
+    #[with(placeholder_sprite)]
+    pub sprite_bundle: SpriteBundle,
+    /// Board pieces move square by square, so they only need a sensor for attacks to land
+    #[from_entity_instance]
+    pub sensor_bundle: SensorBundle,
+    pub enemy: Enemy,
+    #[from_entity_instance]
+    pub chess_piece: ChessPiece,
+    #[from_entity_instance]
+    pub health: Health,
+    pub board_piece: BoardPiece,
+    /// Position on the board, in squares rather than level tiles
+    pub grid_coords: GridCoords,
+
+    #[from_entity_instance]
+    entity_instance: EntityInstance,
+}
+
+/// A piece that moves on a [`ChessBoard`] instead of using physics,
+/// the board is found once the piece spawns
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct BoardPiece {
+    pub board: Option<Entity>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum AttackDirection {
+    #[default]
+    Forward,
+    Up,
+    Down,
+}
+
+#[derive(Clone, Component)]
+pub struct Attacker {
+    pub damage: i32,
+    pub reach: f32,
+    pub pogo_height: f32,
+    pub hit_stop_seconds: f32,
+
+    pub can_attack: bool,
+    pub is_attacking: bool,
+    pub is_recovering: bool,
+    pub attack_direction: AttackDirection,
+    pub attack_timer: Timer,
+    pub recovery_timer: Timer,
+    pub attack_cooldown_timer: Timer,
+}
+
+impl Default for Attacker {
+    fn default() -> Self {
+        Attacker {
+            damage: 1,
+            reach: 24.,
+            pogo_height: 150000.,
+            hit_stop_seconds: 0.08,
+
+            can_attack: true,
+            is_attacking: false,
+            is_recovering: false,
+            attack_direction: AttackDirection::Forward,
+            attack_timer: Timer::from_seconds(0.1, TimerMode::Once),
+            recovery_timer: Timer::from_seconds(0.1, TimerMode::Once),
+            attack_cooldown_timer: Timer::from_seconds(0.25, TimerMode::Once),
+        }
+    }
+}
+
+/// Sensor spawned as a child of an attacker for as long as its attack is active
+#[derive(Component)]
+pub struct AttackHitbox {
+    pub attacker_entity: Entity,
+    pub direction: AttackDirection,
+    pub damage: i32,
+    pub hit_entities: HashSet<Entity>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum BehaviourState {
+    #[default]
+    Idle,
+    Patrol,
+    Chase,
+    Attack,
+    Stunned,
+    Dead,
+}
+
+/// Walks back and forth around where the enemy spawned.
+/// Without a distance the enemy keeps going forward and never turns around, like a pawn
+#[derive(Clone, Debug)]
+pub struct PatrolBehaviour {
+    pub speed: f32,
+    pub distance: Option<f32>,
+}
+
+/// Runs at the player once they are seen, giving up after losing sight of them for a while
+#[derive(Clone, Debug)]
+pub struct ChaseBehaviour {
+    pub speed: f32,
+    pub give_up_seconds: f32,
+}
+
+/// Winds up, then lunges at the player once they are in range
+#[derive(Clone, Debug)]
+pub struct AttackBehaviour {
+    pub range: f32,
+    pub windup_seconds: f32,
+    pub lunge_seconds: f32,
+    pub lunge_speed: f32,
+    /// Shakes the screen as the lunge starts, for the heavy hitters
+    pub slam_trauma: f32,
+}
+
+/// The reusable behaviours a kind of enemy is made of
+#[derive(Clone, Debug)]
+pub struct BehaviourConfig {
+    pub patrol: Option<PatrolBehaviour>,
+    pub chase: Option<ChaseBehaviour>,
+    pub attack: Option<AttackBehaviour>,
+
+    pub aggro_radius: f32,
+    pub stun_seconds: f32,
+    pub death_seconds: f32,
+    pub contact_damage: i32,
+    /// Bosses take over the music while they fight the player
+    pub boss_music: Option<String>,
+    /// Bosses stay dead once beaten, they're recorded in the save
+    pub is_boss: bool,
+}
+
+impl Default for BehaviourConfig {
+    fn default() -> Self {
+        BehaviourConfig {
+            patrol: None,
+            chase: None,
+            attack: None,
+
+            aggro_radius: 200.,
+            stun_seconds: 0.3,
+            death_seconds: 0.5,
+            contact_damage: 1,
+            boss_music: None,
+            is_boss: false,
+        }
+    }
+}
+
+impl BehaviourConfig {
+    /// Overrides the config with any of the behaviour fields set on the LDtk entity
+    pub fn with_entity_fields(mut self, entity_instance: &EntityInstance) -> BehaviourConfig {
+        if let Ok(Some(aggro_radius)) = entity_instance.get_maybe_float_field("Aggro_radius") {
+            self.aggro_radius = *aggro_radius;
+        }
+        if let Ok(Some(speed)) = entity_instance.get_maybe_float_field("Speed") {
+            if let Some(patrol) = &mut self.patrol {
+                patrol.speed = *speed;
+            }
+            if let Some(chase) = &mut self.chase {
+                chase.speed = *speed;
+            }
+        }
+        if let (Some(patrol), Ok(Some(distance))) = (&mut self.patrol, entity_instance.get_maybe_float_field("Patrol_distance")) {
+            patrol.distance = Some(*distance);
+        }
+
+        self
+    }
+}
+
+#[derive(Clone, Component)]
+pub struct Behaviour {
+    pub config: BehaviourConfig,
+    pub state: BehaviourState,
+    pub state_timer: Timer,
+    pub facing_direction: FacingDirection,
+    pub home: Vec2,
+}
+
+impl Behaviour {
+    pub fn new(config: BehaviourConfig, facing_direction: FacingDirection, home: Vec2) -> Behaviour {
+        let mut behaviour = Behaviour {
+            config,
+            state: BehaviourState::Idle,
+            state_timer: Timer::from_seconds(0.0, TimerMode::Once),
+            facing_direction,
+            home,
+        };
+        behaviour.set_state(behaviour.resting_state(), 0.0);
+        behaviour
+    }
+
+    /// The state the enemy goes back to when it has nothing better to do
+    pub fn resting_state(&self) -> BehaviourState {
+        if self.config.patrol.is_some() {
+            BehaviourState::Patrol
+        } else {
+            BehaviourState::Idle
+        }
+    }
+
+    pub fn set_state(&mut self, state: BehaviourState, seconds: f32) {
+        self.state = state;
+        self.state_timer = Timer::from_seconds(seconds, TimerMode::Once);
+    }
+}
+
+/// What an enemy knows about the player this frame
+#[derive(Clone, Default, Component)]
+pub struct Perception {
+    pub sees_player: bool,
+    pub player_offset: Vec2,
+}
+
+/// Path an enemy is following to reach the player
+#[derive(Clone, Component)]
+pub struct Navigator {
+    pub path: Vec<NavLink>,
+    pub waypoint: Option<Vec2>,
+    pub repath_timer: Timer,
+}
+
+impl Default for Navigator {
+    fn default() -> Self {
+        Navigator {
+            path: Vec::new(),
+            waypoint: None,
+            repath_timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct DoorBundle {
+    #[with(placeholder_sprite)]
+    pub sprite_bundle: SpriteBundle,
+    #[from_entity_instance]
+    pub door: Door,
+}
+
+/// Pressing up in front of a door warps the player to the door it references,
+/// which can be in any level
+#[derive(Clone, Default, Component)]
+pub struct Door {
+    pub half_extents: Vec2,
+    pub target_level: Option<LevelSelection>,
+    pub target_door_iid: Option<String>,
+}
+
+impl From<&EntityInstance> for Door {
+    fn from(entity_instance: &EntityInstance) -> Door {
+        let target_door = entity_instance.get_maybe_entity_ref_field("Target_door").ok().cloned().flatten();
+
+        // an explicit level identifier wins, otherwise go to the level of the target door
+        let target_level = match entity_instance.get_maybe_string_field("Target_level") {
+            Ok(Some(identifier)) => Some(LevelSelection::Identifier(identifier.clone())),
+            _ => target_door.as_ref().map(|door| LevelSelection::Iid(door.level_iid.clone())),
+        };
+
+        Door {
+            half_extents: Vec2::new(entity_instance.width as f32, entity_instance.height as f32) / 2.,
+            target_level,
+            target_door_iid: target_door.map(|door| door.entity_iid),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct ScreenFade;
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct AbilityPickupBundle {
+    #[with(placeholder_sprite)]
+    pub sprite_bundle: SpriteBundle,
+    #[from_entity_instance]
+    pub ability_pickup: AbilityPickup,
+}
+
+/// Unlocks an ability when the player touches it, set by the `Ability` enum field in LDtk
+#[derive(Clone, Default, Component)]
+pub struct AbilityPickup {
+    pub half_extents: Vec2,
+    pub ability: Option<Ability>,
+}
+
+impl From<&EntityInstance> for AbilityPickup {
+    fn from(entity_instance: &EntityInstance) -> AbilityPickup {
+        AbilityPickup {
+            half_extents: Vec2::new(entity_instance.width as f32, entity_instance.height as f32) / 2.,
+            ability: entity_instance
+                .get_maybe_enum_field("Ability")
+                .ok()
+                .cloned()
+                .flatten()
+                .and_then(|identifier| Ability::from_identifier(&identifier)),
+        }
+    }
+}
+
+/// A collider that only reports what overlaps it, sized to the LDtk entity
+#[derive(Clone, Default, Bundle)]
+pub struct SensorBundle {
+    pub collider: Collider,
+    pub sensor: Sensor,
+    pub active_events: ActiveEvents,
+}
+
+impl From<&EntityInstance> for SensorBundle {
+    fn from(entity_instance: &EntityInstance) -> SensorBundle {
+        SensorBundle {
+            collider: Collider::cuboid(entity_instance.width as f32 / 2., entity_instance.height as f32 / 2.),
+            sensor: Sensor,
+            active_events: ActiveEvents::COLLISION_EVENTS,
+        }
+    }
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct CollectibleBundle {
+    #[with(placeholder_sprite)]
+    pub sprite_bundle: SpriteBundle,
+    #[from_entity_instance]
+    pub sensor_bundle: SensorBundle,
+    #[from_entity_instance]
+    pub collectible: Collectible,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub enum CollectibleKind {
+    #[default]
+    DreamShard,
+    /// Hidden away somewhere in each level
+    ChessPiece,
+}
+
+impl CollectibleKind {
+    /// Matches the values of the `Collectible_type` enum in LDtk
+    pub fn from_identifier(identifier: &str) -> Option<CollectibleKind> {
+        match identifier {
+            "Dream_shard" => Some(CollectibleKind::DreamShard),
+            "Chess_piece" => Some(CollectibleKind::ChessPiece),
+            _ => None,
+        }
+    }
+
+    pub fn from_entity_instance(entity_instance: &EntityInstance) -> CollectibleKind {
+        match entity_instance.get_maybe_enum_field("Collectible_type") {
+            Ok(Some(identifier)) => CollectibleKind::from_identifier(identifier).unwrap_or_default(),
+            _ => CollectibleKind::DreamShard,
+        }
+    }
+}
+
+/// Picked up by touching it, and gone for good once it's in the save
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Collectible {
+    pub kind: CollectibleKind,
+}
+
+impl From<&EntityInstance> for Collectible {
+    fn from(entity_instance: &EntityInstance) -> Collectible {
+        Collectible {
+            kind: CollectibleKind::from_entity_instance(entity_instance),
+        }
+    }
+}
+
+/// Root of the banner shown while an ability is being picked up
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct AbilityBanner;
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct CameraZoneBundle {
+    #[from_entity_instance]
+    pub camera_zone: CameraZone,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum CameraZoneMode {
+    /// Keeps following the player, for zones that only change the zoom
+    #[default]
+    Follow,
+    /// Locks the camera to the center of the zone
+    Arena,
+    FixedX,
+    FixedY,
+    /// Follows the player along the zone's longest axis, through its center
+    Rail,
+}
+
+impl CameraZoneMode {
+    /// Matches the values of the `Camera_mode` enum in the LDtk project
+    pub fn from_identifier(identifier: &str) -> Option<CameraZoneMode> {
+        match identifier {
+            "Follow" => Some(CameraZoneMode::Follow),
+            "Arena" => Some(CameraZoneMode::Arena),
+            "Fixed_x" => Some(CameraZoneMode::FixedX),
+            "Fixed_y" => Some(CameraZoneMode::FixedY),
+            "Rail" => Some(CameraZoneMode::Rail),
+            _ => None,
+        }
+    }
+}
+
+/// Overrides how the camera follows the player while the player is inside it
+#[derive(Clone, Default, Component)]
+pub struct CameraZone {
+    pub half_extents: Vec2,
+    pub mode: CameraZoneMode,
+    pub scale: Option<f32>,
+    pub blend_seconds: f32,
+}
+
+impl CameraZone {
+    /// Where the camera should look, given where it would look without the zone
+    pub fn constrain(&self, center: Vec2, target: Vec2) -> Vec2 {
+        match self.mode {
+            CameraZoneMode::Follow => target,
+            CameraZoneMode::Arena => center,
+            CameraZoneMode::FixedX => Vec2::new(center.x, target.y),
+            CameraZoneMode::FixedY => Vec2::new(target.x, center.y),
+            CameraZoneMode::Rail => {
+                if self.half_extents.x >= self.half_extents.y {
+                    Vec2::new(target.x.clamp(center.x - self.half_extents.x, center.x + self.half_extents.x), center.y)
+                } else {
+                    Vec2::new(center.x, target.y.clamp(center.y - self.half_extents.y, center.y + self.half_extents.y))
+                }
+            }
+        }
+    }
+}
+
+impl From<&EntityInstance> for CameraZone {
+    fn from(entity_instance: &EntityInstance) -> CameraZone {
+        let mode = match entity_instance.get_maybe_enum_field("Camera_mode") {
+            Ok(Some(mode)) => CameraZoneMode::from_identifier(mode).unwrap_or_default(),
+            _ => CameraZoneMode::Follow,
+        };
+
+        CameraZone {
+            half_extents: Vec2::new(entity_instance.width as f32, entity_instance.height as f32) / 2.,
+            mode,
+            scale: entity_instance.get_maybe_float_field("Zoom").ok().copied().flatten(),
+            blend_seconds: entity_instance.get_maybe_float_field("Blend_seconds").ok().copied().flatten().unwrap_or(0.5),
+        }
+    }
+}
+
+/// One layer of a level's backdrop, see [`BackdropRegistry`](crate::resources::BackdropRegistry)
+#[derive(Clone, Debug)]
+pub struct ParallaxLayerConfig {
+    pub image: String,
+    /// How much the layer moves with the world, 1 moves along with the level and 0 stays fixed to the camera
+    pub scroll_factor: Vec2,
+    pub scale: f32,
+    pub tile_x: bool,
+    pub tile_y: bool,
+    /// Keeps the layer vertically centered on the camera however far it scrolls
+    pub lock_vertical: bool,
+    pub offset: Vec2,
+    pub color: Color,
+}
+
+/// Root of the current level's backdrop, so switching backdrops can despawn all of its layers at once
+#[derive(Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Backdrop {
+    /// Name it is registered under in the [`BackdropRegistry`](crate::resources::BackdropRegistry)
+    pub name: String,
+}
+
+/// Tiles of the layer are spawned as children once its image has loaded
+#[derive(Clone, Component)]
+pub struct ParallaxLayer {
+    pub config: ParallaxLayerConfig,
+    pub image: Handle<Image>,
+    pub tile_size: Option<Vec2>,
+    pub tiled_area: Vec2,
+}
+
+/// A music track that is playing, fading in until it reaches full volume
+/// or fading out until it is silent and gets despawned
+#[derive(Clone, Debug, Component)]
+pub struct MusicPlayer {
+    pub name: String,
+    pub volume: f32,
+    pub fade: f32,
+    pub is_fading_out: bool,
+}
+
+/// A sound that keeps playing until stopped, like footsteps or a wall slide.
+/// Despawning the entity stops the sound
+#[derive(Clone, Debug, Component)]
+pub struct LoopingSound {
+    pub name: String,
+}
+
+/// Plays clips from an [`AnimationSheet`] on the entity's texture atlas sprite.
+/// The texture atlas is built from the sheet once it has loaded
+#[derive(Clone, Component)]
+pub struct Animator {
+    pub sheet: Handle<AnimationSheet>,
+    pub clip: String,
+    pub frame: usize,
+    pub frame_timer: Timer,
+    pub is_finished: bool,
+    /// Set by cutscenes to play a clip whatever the player is doing
+    pub scripted_clip: Option<String>,
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Animator {
+            sheet: Handle::default(),
+            clip: "Idle".to_string(),
+            frame: 0,
+            frame_timer: Timer::from_seconds(0.0, TimerMode::Repeating),
+            is_finished: false,
+            scripted_clip: None,
+        }
+    }
+}
+
+impl Animator {
+    /// Starts the clip from its first frame, unless it is already playing
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            self.clip = clip.to_string();
+            self.frame = 0;
+            self.frame_timer.reset();
+            self.is_finished = false;
+        }
+    }
+}
+
+/// Root of the main menu UI, despawned when leaving the menu
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct MainMenuScreen;
+
+/// Root of the game over UI
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct GameOverScreen;
+
+/// Root of the pause menu UI
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct PauseScreen;
+
+/// Root of the settings UI, opened from the pause menu or the main menu
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct SettingsScreen;
+
+/// Root of the save slot picker, opened by playing from the main menu
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct SaveSlotsScreen;
+
+/// Root of the key bindings UI, opened from the settings
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct KeyBindingsScreen;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum VolumeChannel {
+    Master,
+    Music,
+    Sfx,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DisplayOption {
+    Scale,
+    Fullscreen,
+    Vsync,
+    AlwaysOnTop,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MenuAction {
+    Play,
+    Retry,
+    Resume,
+    Restart,
+    Settings,
+    Back,
+    Volume(VolumeChannel),
+    Display(DisplayOption),
+    KeyBindings,
+    Rebind(InputAction),
+    SaveSlot(usize),
+    MainMenu,
+    Quit,
+}
+
+/// A list of buttons that can be moved through with the arrow keys
+#[derive(Clone, Debug, Default, Component)]
+pub struct Menu {
+    pub selected: usize,
+}
+
+/// One of the buttons of a [`Menu`], in the order they are listed
+#[derive(Copy, Clone, Debug, Component)]
+pub struct MenuButton {
+    pub index: usize,
+    pub action: MenuAction,
+}
+
+/// Root of the loading screen UI
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct LoadingScreen;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct LoadingProgressText;
+
+/// Root of the in-game HUD, spawned with the world
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Hud;
+
+/// Holds a pip for each point of the player's max health
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct HealthPips;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct HealthPip {
+    pub index: i32,
+}
+
+/// Hidden until the dash is unlocked
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct DashIndicator;
+
+/// Fills up as the dash cools down
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct DashIndicatorFill;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct CollectibleCounter;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct LevelNameText;
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct NpcBundle {
+    #[with(placeholder_sprite)]
+    pub sprite_bundle: SpriteBundle,
+    #[from_entity_instance]
+    pub npc: Npc,
+}
+
+/// Talks when the player presses up next to them, playing `assets/dialogue/<Dialogue_id>.dialogue.ron`
+#[derive(Clone, Default, Component)]
+pub struct Npc {
+    pub dialogue_id: String,
+    pub half_extents: Vec2,
+    pub script: Handle<DialogueScript>,
+}
+
+impl From<&EntityInstance> for Npc {
+    fn from(entity_instance: &EntityInstance) -> Npc {
+        Npc {
+            dialogue_id: entity_instance.get_maybe_string_field("Dialogue_id").ok().cloned().flatten().unwrap_or_default(),
+            half_extents: Vec2::new(entity_instance.width as f32, entity_instance.height as f32) / 2.,
+            script: Handle::default(),
+        }
+    }
+}
+
+/// Shown above an NPC while the player is close enough to talk
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct NpcPrompt;
+
+/// Root of the dialogue box UI
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct DialogueBox;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct DialoguePortrait;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct DialogueSpeakerText;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct DialogueText;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct DialogueChoicesText;
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct TriggerBundle {
+    #[from_entity_instance]
+    pub trigger: Trigger,
+}
+
+/// Plays `assets/cutscenes/<Cutscene>.cutscene.ron` when the player walks into the region
+#[derive(Clone, Default, Component)]
+pub struct Trigger {
+    pub cutscene_id: String,
+    pub half_extents: Vec2,
+    /// Only ever plays once per save, otherwise again every time the player walks back in
+    pub once: bool,
+    pub is_player_inside: bool,
+    pub script: Handle<CutsceneScript>,
+}
+
+impl From<&EntityInstance> for Trigger {
+    fn from(entity_instance: &EntityInstance) -> Trigger {
+        Trigger {
+            cutscene_id: entity_instance.get_maybe_string_field("Cutscene").ok().cloned().flatten().unwrap_or_default(),
+            half_extents: Vec2::new(entity_instance.width as f32, entity_instance.height as f32) / 2.,
+            once: entity_instance.get_bool_field("Once").ok().copied().unwrap_or(true),
+            is_player_inside: false,
+            script: Handle::default(),
+        }
+    }
+}
+
+/// Spawned by a cutscene, and despawned along with the level it was spawned in
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct CutsceneSprite;
+
+/// Root of the skip hint shown while a cutscene plays
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct CutsceneHint;
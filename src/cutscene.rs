@@ -0,0 +1,50 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+/// One step of a cutscene. Positions are relative to the center of the trigger that started it
+#[derive(Clone, Debug, Deserialize)]
+pub enum CutsceneStep {
+    /// Pans the camera over `seconds` and keeps it there until `ReleaseCamera` or the end of the cutscene
+    MoveCamera { to: (f32, f32), seconds: f32 },
+    ReleaseCamera,
+    /// Walks the player along the ground until they reach the x position
+    WalkPlayer { to_x: f32 },
+    PlayAnimation { clip: String, seconds: f32 },
+    /// Plays `assets/dialogue/<id>.dialogue.ron` and waits for it to finish
+    ShowDialogue { id: String },
+    Wait { seconds: f32 },
+    /// Spawns an image into the level, which stays there until the level unloads
+    Spawn { image: String, at: (f32, f32), size: (f32, f32) },
+    /// Fades the screen to black, 1 is fully faded out and 0 fades back in
+    Fade { to: f32, seconds: f32 },
+}
+
+/// A timeline of steps played one after another, loaded from `.cutscene.ron` files
+#[derive(Clone, Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "a3c6f0d2-6b1e-4c8f-9e57-2d8b4f1a9c33"]
+pub struct CutsceneScript {
+    pub steps: Vec<CutsceneStep>,
+}
+
+#[derive(Default)]
+pub struct CutsceneScriptLoader;
+
+impl AssetLoader for CutsceneScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let script: CutsceneScript = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(script));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cutscene.ron"]
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use std::collections::{HashMap, HashSet};
+
+/// One of the answers offered at the end of a line
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// Ends the conversation when there is none
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Only offered once this flag has been set
+    #[serde(default)]
+    pub requires_flag: Option<String>,
+    #[serde(default)]
+    pub set_flags: Vec<String>,
+}
+
+impl DialogueChoice {
+    pub fn is_available(&self, flags: &HashSet<String>) -> bool {
+        match &self.requires_flag {
+            Some(flag) => flags.contains(flag),
+            None => true,
+        }
+    }
+}
+
+/// A single line, followed by either its choices or the next line
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueLine {
+    pub speaker: String,
+    /// Image path, relative to the assets folder
+    #[serde(default)]
+    pub portrait: Option<String>,
+    pub text: String,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+    /// Ends the conversation when there is none, and there are no choices
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Set as soon as the line is shown
+    #[serde(default)]
+    pub set_flags: Vec<String>,
+}
+
+/// Starts the conversation somewhere else once a flag has been set, so NPCs remember having talked before
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueStart {
+    pub flag: String,
+    pub line: String,
+}
+
+/// A branching conversation of named lines, loaded from `.dialogue.ron` files
+#[derive(Clone, Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "5b0e4b1c-8f55-4d4a-a0d9-3c4f1e6b7a21"]
+pub struct DialogueScript {
+    pub start: String,
+    /// Checked in order, the first one with its flag set wins over `start`
+    #[serde(default)]
+    pub revisits: Vec<DialogueStart>,
+    pub lines: HashMap<String, DialogueLine>,
+}
+
+impl DialogueScript {
+    pub fn start_line(&self, flags: &HashSet<String>) -> &str {
+        self.revisits
+            .iter()
+            .find(|revisit| flags.contains(&revisit.flag))
+            .map_or(&self.start, |revisit| &revisit.line)
+    }
+}
+
+#[derive(Default)]
+pub struct DialogueScriptLoader;
+
+impl AssetLoader for DialogueScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let script: DialogueScript = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(script));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.ron"]
+    }
+}
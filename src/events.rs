@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+
+use crate::components::MenuAction;
+
+/// Sent by anything that wants to hurt an entity with [`Health`](crate::components::Health)
+#[derive(Copy, Clone, Debug, Event)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: i32,
+}
+
+/// Things the player does that other systems, like audio, react to
+#[derive(Copy, Clone, Debug, PartialEq, Event)]
+pub enum PlayerEvent {
+    Jumped,
+    WallJumped,
+    Landed { impact_speed: f32 },
+    Dashed,
+    WallSlideStarted,
+    WallSlideStopped,
+    Hurt,
+    Collected,
+}
+
+/// Sent when a menu button is picked, by keyboard or by mouse.
+/// Left and right on a button send a step of -1 or 1, picking it sends 0
+#[derive(Copy, Clone, Debug, PartialEq, Event)]
+pub struct MenuEvent {
+    pub action: MenuAction,
+    pub step: i32,
+}
+
+/// Adds trauma to the [`ScreenShake`](crate::resources::ScreenShake), from 0 for none to 1 for the most violent shake
+#[derive(Copy, Clone, Debug, Event)]
+pub struct ScreenShakeEvent {
+    pub trauma: f32,
+}
+
+/// Freezes gameplay time through the [`HitStop`](crate::resources::HitStop)
+#[derive(Copy, Clone, Debug, Event)]
+pub struct HitStopEvent {
+    pub seconds: f32,
+}
@@ -1,69 +1,326 @@
-use bevy::{
-    prelude::*,
-    window::{PresentMode, WindowLevel},};
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 
+use states::{GameState, GameplaySet};
+
+mod animation;
 mod components;
+mod cutscene;
+mod dialogue;
+mod events;
+mod nav_graph;
+mod resources;
+mod save;
+mod settings;
+mod states;
+mod storage;
 mod systems;
 
-const WIDTH: f32 = 640.;
-const HEIGHT: f32 = 480.;
-
 //Main
 fn main() {
+    // a broken settings file shouldn't stop the game from starting, the defaults will do
+    let (settings, settings_error) = match settings::Settings::load() {
+        Ok(settings) => (settings, None),
+        Err(error) => (settings::Settings::default(), Some(error.to_string())),
+    };
+
+    let mut window = Window {
+        title: "Enter the Dreamer Sandbox".into(),
+        resizable: false,
+        focused: true,
+        ..default()
+    };
+    settings.display.apply(&mut window);
+
     App::new()
         .add_plugins((DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window{
-                title: "Enter the Dreamer Sandbox".into(),
-                resolution: (WIDTH, HEIGHT).into(),
-                present_mode: PresentMode::AutoVsync,
-                window_level: WindowLevel::AlwaysOnTop,
-                resizable: false,
-                focused: true,
-                ..default()
-            }),
+            primary_window: Some(window),
             ..default()
         }).set(ImagePlugin::default_nearest()),
         RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.), 
         LdtkPlugin,
         RapierDebugRenderPlugin::default(),
         LogDiagnosticsPlugin::default(),
-        FrameTimeDiagnosticsPlugin::default(),
+        FrameTimeDiagnosticsPlugin,
         ))
         .insert_resource(ClearColor(Color::rgb(0.04, 0.04, 0.04))) //changes background color
         .insert_resource(RapierConfiguration {
             gravity: Vec2::new(0.0, -2000.0),
             ..Default::default()
         })
+        //Game States
+        .add_state::<GameState>()
+        .configure_set(Update, GameplaySet.run_if(in_state(GameState::Playing)))
+        .add_event::<events::MenuEvent>()
+        .add_systems(OnEnter(GameState::Loading), systems::loading::start_loading)
+        .add_systems(Update, systems::loading::track_loading.run_if(in_state(GameState::Loading)))
+        .add_systems(OnExit(GameState::Loading), systems::menu::despawn_screen::<components::LoadingScreen>)
+        .add_systems(OnEnter(GameState::MainMenu), systems::game_state::despawn_world)
+        .add_systems(OnEnter(GameState::MainMenu), systems::game_state::reset_progress)
+        .add_systems(OnEnter(GameState::MainMenu), systems::menu::spawn_main_menu)
+        .add_systems(OnExit(GameState::MainMenu), systems::menu::despawn_screen::<components::MainMenuScreen>)
+        .add_systems(OnExit(GameState::MainMenu), systems::menu::despawn_screen::<components::SaveSlotsScreen>)
+        .add_systems(OnExit(GameState::MainMenu), systems::menu::despawn_screen::<components::SettingsScreen>)
+        .add_systems(OnExit(GameState::MainMenu), systems::menu::despawn_screen::<components::KeyBindingsScreen>)
+        .add_systems(OnEnter(GameState::Playing), systems::save::restore_save.before(systems::game_state::spawn_world))
+        .add_systems(OnEnter(GameState::Playing), systems::game_state::spawn_world)
+        .add_systems(OnEnter(GameState::Paused), systems::game_state::pause_game)
+        .add_systems(OnEnter(GameState::Paused), systems::menu::spawn_pause_menu)
+        .add_systems(OnExit(GameState::Paused), systems::game_state::resume_game)
+        .add_systems(OnExit(GameState::Paused), systems::menu::despawn_screen::<components::PauseScreen>)
+        .add_systems(OnExit(GameState::Paused), systems::menu::despawn_screen::<components::SettingsScreen>)
+        .add_systems(OnExit(GameState::Paused), systems::menu::despawn_screen::<components::KeyBindingsScreen>)
+        .add_systems(OnEnter(GameState::GameOver), systems::menu::spawn_game_over)
+        .add_systems(OnExit(GameState::GameOver), systems::menu::despawn_screen::<components::GameOverScreen>)
+        .add_systems(OnExit(GameState::GameOver), systems::game_state::despawn_world)
+        .add_systems(OnExit(GameState::GameOver), systems::game_state::reset_progress)
+        .add_systems(Update, systems::game_state::toggle_pause)
+        .add_systems(Update, systems::game_state::player_death.in_set(GameplaySet))
+        .add_systems(Update, systems::menu::menu_navigation)
+        .add_systems(Update, systems::menu::menu_actions)
+        .add_systems(Update, systems::menu::settings_actions)
+        .add_systems(Update, systems::menu::update_setting_labels)
+
+        //Settings
+        .insert_resource(settings.display)
+        .insert_resource(settings.volume)
+        .insert_resource(settings.key_bindings)
+        .init_resource::<resources::KeyRebinding>()
+        // logging only starts along with the app, so the error waits until then
+        .add_systems(Startup, move || {
+            if let Some(error) = &settings_error {
+                warn!("Couldn't load the settings, using the defaults: {}", error);
+            }
+        })
+        .add_systems(Update, systems::settings::apply_display_settings)
+        .add_systems(Update, systems::settings::save_settings)
+        .add_systems(Update, systems::settings::capture_key_binding)
+
+        //Saving
+        .init_resource::<resources::ActiveSave>()
+        .init_resource::<resources::UnlockedAbilities>()
+        .init_resource::<resources::Progress>()
+        .add_systems(Update, systems::save::pick_save_slot)
+        .add_systems(Update, systems::save::autosave)
+        .add_systems(OnTransition { from: GameState::Paused, to: GameState::MainMenu }, systems::save::save_on_quit)
+        .add_systems(Update, (
+            systems::save::track_play_time,
+            systems::save::record_defeated_bosses,
+            systems::save::despawn_defeated_bosses,
+        ).in_set(GameplaySet))
+
+        //Abilities
+        .init_resource::<resources::InputLock>()
+        .init_resource::<resources::AbilityCutscene>()
+        .add_systems(Update, (
+            systems::ability::collect_ability_pickups,
+            systems::ability::despawn_unlocked_pickups,
+            systems::ability::ability_cutscene,
+        ).in_set(GameplaySet))
+
+        //Collectibles
+        .add_systems(Update, (
+            systems::collectible::collect_collectibles,
+            systems::collectible::despawn_collected,
+        ).in_set(GameplaySet))
+
+        //Dialogue
+        .add_asset::<dialogue::DialogueScript>()
+        .init_asset_loader::<dialogue::DialogueScriptLoader>()
+        .init_resource::<resources::DialogueState>()
+        .add_systems(OnEnter(GameState::MainMenu), systems::menu::despawn_screen::<components::DialogueBox>)
+        .add_systems(OnExit(GameState::GameOver), systems::menu::despawn_screen::<components::DialogueBox>)
+        .add_systems(Update, (
+            systems::dialogue::load_npc_dialogue,
+            systems::dialogue::npc_prompts,
+            systems::dialogue::start_dialogue,
+            systems::dialogue::advance_dialogue,
+            systems::dialogue::update_dialogue_box,
+        ).in_set(GameplaySet))
+
+        //Cutscenes
+        .add_asset::<cutscene::CutsceneScript>()
+        .init_asset_loader::<cutscene::CutsceneScriptLoader>()
+        .init_resource::<resources::CutsceneState>()
+        .init_resource::<resources::ScriptedInput>()
+        .add_systems(OnEnter(GameState::MainMenu), systems::menu::despawn_screen::<components::CutsceneHint>)
+        .add_systems(OnExit(GameState::GameOver), systems::menu::despawn_screen::<components::CutsceneHint>)
+        .add_systems(Update, (
+            systems::cutscene::load_trigger_cutscenes,
+            systems::cutscene::start_cutscenes,
+        ).in_set(GameplaySet))
+        .add_systems(Update, (
+            systems::cutscene::skip_cutscene,
+            systems::cutscene::run_cutscene,
+            systems::cutscene::finish_cutscene,
+        ).chain().in_set(GameplaySet))
+
+        //HUD
+        .add_systems(OnEnter(GameState::Playing), systems::hud::spawn_hud)
+        .add_systems(OnEnter(GameState::MainMenu), systems::menu::despawn_screen::<components::Hud>)
+        .add_systems(OnExit(GameState::GameOver), systems::menu::despawn_screen::<components::Hud>)
+        .add_systems(Update, (
+            systems::hud::update_health_pips,
+            systems::hud::update_dash_indicator,
+            systems::hud::update_collectible_counter,
+            systems::hud::update_level_name,
+        ).in_set(GameplaySet))
+
         //Set Up
         .add_systems(Startup, systems::setup::basic_setup)
-        .add_systems(Update, systems::setup::spawn_wall_collision)
-        .add_systems(Update, systems::setup::spawn_ground_sensor)
-        .add_systems(Update, systems::setup::spawn_wall_sensor)
+        .add_systems(Update, (
+            systems::setup::spawn_wall_collision,
+            systems::setup::spawn_ground_sensor,
+            systems::setup::spawn_wall_sensor,
+        ).in_set(GameplaySet))
 
         //Wall/Ground Detection
-        .add_systems(Update, systems::detection::ground_detection)
-        .add_systems(Update, systems::detection::update_on_ground)
-        .add_systems(Update, systems::detection::wall_detection)
-        .add_systems(Update, systems::detection::update_on_wall)
+        .add_systems(Update, (
+            systems::detection::ground_detection,
+            systems::detection::update_on_ground,
+            systems::detection::wall_detection,
+            systems::detection::update_on_wall,
+        ).in_set(GameplaySet))
+
+        //Levels
+        .init_resource::<resources::Checkpoint>()
+        .add_systems(Update, (
+            systems::level::update_level_selection,
+            systems::level::update_checkpoint,
+            systems::level::spawn_at_checkpoint,
+        ).in_set(GameplaySet))
+        .add_systems(Update, systems::level::restart_from_checkpoint.after(systems::menu::menu_navigation))
+
+        //Doors
+        .init_resource::<resources::DoorTransition>()
+        .add_systems(Update, (
+            systems::door::enter_doors,
+            systems::door::door_transition,
+        ).in_set(GameplaySet))
+        .add_systems(Update, systems::door::draw_screen_fade)
 
         //camera
-        .add_systems(Update, systems::camera::player_camera)
+        .add_systems(Update, systems::camera::player_camera.in_set(GameplaySet))
+
+        //Effects
+        .init_resource::<resources::ScreenShake>()
+        .init_resource::<resources::HitStop>()
+        .add_event::<events::ScreenShakeEvent>()
+        .add_event::<events::HitStopEvent>()
+        .add_systems(PreUpdate, systems::effects::remove_screen_shake)
+        .add_systems(Update, (
+            systems::effects::add_trauma,
+            systems::effects::screen_shake.after(systems::camera::player_camera),
+            systems::effects::landing_shake,
+            systems::effects::hit_stop,
+        ).in_set(GameplaySet))
+
+        //Parallax
+        .init_resource::<resources::BackdropRegistry>()
+        .add_systems(Update, systems::parallax::spawn_backdrop)
+        .add_systems(Update, systems::parallax::tile_parallax_layers)
+        .add_systems(Update, systems::parallax::scroll_parallax_layers.after(systems::effects::screen_shake))
+
+        //Animation
+        .add_asset::<animation::AnimationSheet>()
+        .init_asset_loader::<animation::AnimationSheetLoader>()
+        .add_systems(Update, systems::animation::load_player_animations)
+        .add_systems(Update, systems::animation::build_texture_atlases)
+        .add_systems(Update, (
+            systems::animation::player_animation_state,
+            systems::animation::animate_sprites,
+        ).in_set(GameplaySet))
+
+        //Audio
+        .init_resource::<resources::SoundRegistry>()
+        .add_event::<events::PlayerEvent>()
+        .add_systems(Update, systems::audio::player_action_sounds)
+        .add_systems(Update, systems::audio::footsteps.in_set(GameplaySet))
+        .add_systems(Update, systems::audio::looping_sound_volume)
+
+        //Music
+        .init_resource::<resources::MusicRegistry>()
+        .init_resource::<resources::MusicManager>()
+        .add_systems(Update, systems::music::select_level_music)
+        .add_systems(Update, systems::music::boss_music.in_set(GameplaySet))
+        .add_systems(Update, systems::music::crossfade_music)
 
         //Movement
-        .add_systems(Update, systems::movement::horizontal_movement_no_acc)
-        .add_systems(Update, systems::movement::horizontal_dash)
-        .add_systems(Update, systems::movement::vertical_jump)
-        .add_systems(Update, systems::movement::wall_jump)
-        .add_systems(Update, systems::movement::set_jumps)
-        .add_systems(Update, systems::movement::set_player_gravity)
-        .add_systems(Update, systems::movement::wall_slide)
+        .add_systems(Update, (
+            systems::movement::horizontal_movement_no_acc,
+            systems::movement::horizontal_dash,
+            systems::movement::vertical_jump,
+            systems::movement::wall_jump,
+            systems::movement::set_jumps,
+            systems::movement::set_player_gravity,
+            systems::movement::wall_slide,
+        ).in_set(GameplaySet))
+
+        //Enemies
+        .init_resource::<resources::BehaviourRegistry>()
+        .add_systems(Update, (
+            systems::behaviour::configure_behaviours,
+            systems::behaviour::perception,
+            systems::behaviour::behaviour_transitions.before(systems::health::apply_damage),
+            systems::behaviour::behaviour_actions,
+            systems::behaviour::contact_damage,
+            systems::behaviour::despawn_dead_enemies,
+            systems::enemy::pawn_promotion,
+            systems::enemy::animate_promotion,
+        ).in_set(GameplaySet))
+
+        //Navigation
+        .add_systems(Update, (
+            systems::navigation::build_nav_graphs,
+            systems::navigation::plan_paths,
+            systems::navigation::follow_jump_links,
+        ).in_set(GameplaySet))
+
+        //Chessboards
+        .add_systems(Update, (
+            systems::chessboard::attach_board_pieces,
+            systems::chessboard::chessboard_tick,
+            systems::chessboard::render_threat_overlay,
+            systems::chessboard::capture_board_pieces,
+        ).in_set(GameplaySet))
+
+        //Health
+        .add_event::<events::DamageEvent>()
+        .add_systems(Update, (
+            systems::health::apply_damage,
+            systems::health::invulnerability,
+        ).in_set(GameplaySet))
+
+        //Combat
+        .add_systems(Update, (
+            systems::combat::player_attack,
+            systems::combat::attack_hits,
+        ).in_set(GameplaySet))
 
         .register_ldtk_int_cell::<components::WallBundle>(1)
+        .register_ldtk_int_cell::<components::PromotionTileBundle>(2)
         .register_ldtk_entity::<components::PlayerBundle>("Player")
+        .register_ldtk_entity::<components::PawnBundle>("Pawn")
+        .register_ldtk_entity::<components::EnemyBundle>("Enemy")
+        .register_ldtk_entity::<components::ChessBoardBundle>("Chess_board")
+        .register_ldtk_entity::<components::BoardPieceBundle>("Board_piece")
+        .register_ldtk_entity::<components::DoorBundle>("Door")
+        .register_ldtk_entity::<components::CameraZoneBundle>("Camera_zone")
+        .register_ldtk_entity::<components::AbilityPickupBundle>("Ability_pickup")
+        .register_ldtk_entity::<components::CollectibleBundle>("Collectible")
+        .register_ldtk_entity::<components::NpcBundle>("Npc")
+        .register_ldtk_entity::<components::TriggerBundle>("Trigger")
         .insert_resource(LevelSelection::Index(0))
+        .insert_resource(LdtkSettings {
+            level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
+                load_level_neighbors: true,
+            },
+            ..default()
+        })
         .run();
 }
\ No newline at end of file
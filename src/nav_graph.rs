@@ -0,0 +1,264 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::utils::{grid_coords_to_translation, translation_to_grid_coords};
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// The jump enemies are assumed to have when linking platforms, in tiles
+#[derive(Copy, Clone, Debug)]
+pub struct JumpArc {
+    pub height: f32,
+    pub apex_distance: f32,
+}
+
+impl JumpArc {
+    /// The arc of a jump starting at `jump_velocity` under `gravity` while moving at `horizontal_speed`,
+    /// all in pixels and seconds
+    pub fn new(jump_velocity: f32, gravity: f32, horizontal_speed: f32, grid_size: i32) -> JumpArc {
+        let time_to_apex = jump_velocity / gravity;
+        let grid_size = grid_size as f32;
+
+        JumpArc {
+            height: jump_velocity * time_to_apex / 2. / grid_size,
+            apex_distance: horizontal_speed * time_to_apex / grid_size,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NavLinkKind {
+    Walk,
+    Jump,
+    Drop,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct NavLink {
+    pub to: GridCoords,
+    pub kind: NavLinkKind,
+    pub cost: i32,
+}
+
+/// Walkable surfaces of a level and how to get between them.
+/// A node is an empty tile with a wall right below it
+#[derive(Clone, Default, Debug, Component)]
+pub struct NavGraph {
+    pub grid_size: i32,
+    pub nodes: HashSet<GridCoords>,
+    pub links: HashMap<GridCoords, Vec<NavLink>>,
+}
+
+impl NavGraph {
+    pub fn build(walls: &HashSet<GridCoords>, width: i32, height: i32, grid_size: i32, jump_arc: JumpArc) -> NavGraph {
+        let in_bounds = |coords: GridCoords| coords.x >= 0 && coords.x < width && coords.y >= 0 && coords.y < height;
+        let is_open = |coords: GridCoords| in_bounds(coords) && !walls.contains(&coords);
+
+        let mut nodes = HashSet::new();
+        for y in 0..height {
+            for x in 0..width {
+                let coords = GridCoords::new(x, y);
+                if !walls.contains(&coords) && walls.contains(&GridCoords::new(x, y - 1)) {
+                    nodes.insert(coords);
+                }
+            }
+        }
+
+        let mut links: HashMap<GridCoords, Vec<NavLink>> = HashMap::new();
+        for &node in &nodes {
+            let node_links = links.entry(node).or_default();
+
+            for direction in [-1, 1] {
+                let side = GridCoords::new(node.x + direction, node.y);
+
+                // walk along the same surface
+                if nodes.contains(&side) {
+                    node_links.push(NavLink { to: side, kind: NavLinkKind::Walk, cost: 10 });
+                }
+
+                // walk off the edge and fall until landing
+                if is_open(side) && !nodes.contains(&side) {
+                    let mut landing = side;
+                    while is_open(landing) && !nodes.contains(&landing) {
+                        landing.y -= 1;
+                    }
+                    if nodes.contains(&landing) {
+                        let fall = node.y - landing.y;
+                        node_links.push(NavLink { to: landing, kind: NavLinkKind::Drop, cost: 10 + 5 * fall });
+                    }
+                }
+
+                // jump arcs at a few horizontal speeds, landing on the first surface reached on the way down
+                for speed in [0.25, 0.5, 0.75, 1.0] {
+                    let apex = jump_arc.apex_distance * speed;
+                    let mut x = 0.25;
+
+                    while x <= apex * 4. {
+                        let y = jump_arc.height * (1. - ((x - apex) / apex).powi(2));
+                        let coords = GridCoords::new(node.x + direction * x.round() as i32, node.y + y.floor() as i32);
+
+                        if !is_open(coords) {
+                            break;
+                        }
+                        let is_walk = coords.y == node.y && (coords.x - node.x).abs() <= 1;
+                        if x > apex && nodes.contains(&coords) && coords != node && !is_walk {
+                            let distance = (coords.x - node.x).abs();
+                            if !node_links.iter().any(|link| link.to == coords) {
+                                node_links.push(NavLink { to: coords, kind: NavLinkKind::Jump, cost: 20 + 10 * distance });
+                            }
+                            break;
+                        }
+
+                        x += 0.25;
+                    }
+                }
+            }
+        }
+
+        NavGraph {
+            grid_size,
+            nodes,
+            links,
+        }
+    }
+
+    /// The node something standing at the given level translation is on,
+    /// looking a few tiles down for anything in the air
+    pub fn node_below(&self, translation: Vec2) -> Option<GridCoords> {
+        let mut coords = translation_to_grid_coords(translation, IVec2::splat(self.grid_size));
+        for _ in 0..4 {
+            if self.nodes.contains(&coords) {
+                return Some(coords);
+            }
+            coords.y -= 1;
+        }
+        None
+    }
+
+    pub fn node_translation(&self, node: GridCoords) -> Vec2 {
+        grid_coords_to_translation(node, IVec2::splat(self.grid_size))
+    }
+
+    /// A* from one node to another, returning the links to follow in order
+    pub fn find_path(&self, start: GridCoords, goal: GridCoords) -> Option<Vec<NavLink>> {
+        let heuristic = |coords: GridCoords| (((coords.x - goal.x).pow(2) + (coords.y - goal.y).pow(2)) as f32).sqrt() as i32 * 10;
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<GridCoords, (GridCoords, NavLink)> = HashMap::new();
+        let mut best_cost: HashMap<GridCoords, i32> = HashMap::new();
+
+        best_cost.insert(start, 0);
+        open.push(Reverse((heuristic(start), start.x, start.y)));
+
+        while let Some(Reverse((_, x, y))) = open.pop() {
+            let current = GridCoords::new(x, y);
+            if current == goal {
+                let mut path = Vec::new();
+                let mut node = goal;
+                while let Some(&(previous, link)) = came_from.get(&node) {
+                    path.push(link);
+                    node = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let current_cost = best_cost[&current];
+            for link in self.links.get(&current).into_iter().flatten() {
+                let cost = current_cost + link.cost;
+                if cost < *best_cost.get(&link.to).unwrap_or(&i32::MAX) {
+                    best_cost.insert(link.to, cost);
+                    came_from.insert(link.to, (current, *link));
+                    open.push(Reverse((cost + heuristic(link.to), link.to.x, link.to.y)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Path between two level translations, if both are over walkable ground
+    pub fn find_path_between(&self, from: Vec2, to: Vec2) -> Option<Vec<NavLink>> {
+        self.find_path(self.node_below(from)?, self.node_below(to)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(to: (i32, i32), kind: NavLinkKind, cost: i32) -> NavLink {
+        NavLink { to: GridCoords::new(to.0, to.1), kind, cost }
+    }
+
+    /// A floor from (0, 0) to (2, 0) with a ledge at (0, 2) that is jumped up to from the end of the floor,
+    /// and a node at (5, 0) cut off from the rest
+    ///
+    /// ```text
+    /// L
+    ///
+    /// A B C     X
+    /// ```
+    fn graph() -> NavGraph {
+        let links = HashMap::from([
+            (GridCoords::new(0, 0), vec![link((1, 0), NavLinkKind::Walk, 10)]),
+            (GridCoords::new(1, 0), vec![link((0, 0), NavLinkKind::Walk, 10), link((2, 0), NavLinkKind::Walk, 10)]),
+            (GridCoords::new(2, 0), vec![link((1, 0), NavLinkKind::Walk, 10), link((0, 2), NavLinkKind::Jump, 40)]),
+            (GridCoords::new(0, 2), vec![link((0, 0), NavLinkKind::Drop, 20)]),
+            (GridCoords::new(5, 0), vec![]),
+        ]);
+
+        NavGraph {
+            grid_size: 16,
+            nodes: links.keys().copied().collect(),
+            links,
+        }
+    }
+
+    #[test]
+    fn path_to_itself_is_empty() {
+        let start = GridCoords::new(1, 0);
+        assert_eq!(graph().find_path(start, start), Some(vec![]));
+    }
+
+    #[test]
+    fn walks_along_the_floor() {
+        let path = graph().find_path(GridCoords::new(0, 0), GridCoords::new(2, 0)).unwrap();
+        let steps: Vec<GridCoords> = path.iter().map(|link| link.to).collect();
+
+        assert_eq!(steps, vec![GridCoords::new(1, 0), GridCoords::new(2, 0)]);
+        assert!(path.iter().all(|link| link.kind == NavLinkKind::Walk));
+    }
+
+    #[test]
+    fn jumps_up_to_the_ledge() {
+        let path = graph().find_path(GridCoords::new(0, 0), GridCoords::new(0, 2)).unwrap();
+
+        assert_eq!(path.len(), 3);
+        assert_eq!(path.last(), Some(&link((0, 2), NavLinkKind::Jump, 40)));
+    }
+
+    #[test]
+    fn takes_the_cheaper_route() {
+        let mut graph = graph();
+        // a long way round that is still more expensive than dropping from the ledge
+        graph.links.get_mut(&GridCoords::new(0, 2)).unwrap().push(link((2, 0), NavLinkKind::Drop, 50));
+
+        let path = graph.find_path(GridCoords::new(0, 2), GridCoords::new(1, 0)).unwrap();
+        assert_eq!(path, vec![link((0, 0), NavLinkKind::Drop, 20), link((1, 0), NavLinkKind::Walk, 10)]);
+    }
+
+    #[test]
+    fn unreachable_goal_has_no_path() {
+        assert_eq!(graph().find_path(GridCoords::new(0, 0), GridCoords::new(5, 0)), None);
+    }
+
+    #[test]
+    fn jump_arc_matches_the_physics() {
+        // 320 px/s under 1000 px/s² rises 51.2 px in 0.32 s
+        let arc = JumpArc::new(320., 1000., 100., 16);
+
+        assert!((arc.height - 3.2).abs() < 1e-4);
+        assert!((arc.apex_distance - 2.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,651 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::animation::AnimationSheet;
+use crate::cutscene::CutsceneScript;
+use crate::dialogue::DialogueScript;
+use crate::components::*;
+use crate::settings::InputAction;
+
+use std::collections::{HashMap, HashSet};
+
+/// Assets loaded up front by the loading screen, so gameplay never has to wait on them
+#[derive(Resource, Default)]
+pub struct GameAssets {
+    pub ldtk: Handle<LdtkAsset>,
+    pub player_animations: Handle<AnimationSheet>,
+}
+
+/// Everything the loading screen is waiting on.
+/// Gameplay can't start without the required assets, missing optional ones (like sounds) are only reported
+#[derive(Resource, Default)]
+pub struct LoadingTracker {
+    pub required: Vec<(String, HandleUntyped)>,
+    pub optional: Vec<(String, HandleUntyped)>,
+    pub reported_failures: Vec<String>,
+    pub has_ldtk_dependencies: bool,
+    pub has_animation_dependencies: bool,
+    pub has_failed: bool,
+}
+
+impl LoadingTracker {
+    pub fn require<T: bevy::asset::Asset>(&mut self, path: &str, handle: &Handle<T>) {
+        self.required.push((path.to_string(), handle.clone_untyped()));
+    }
+
+    pub fn want<T: bevy::asset::Asset>(&mut self, path: &str, handle: &Handle<T>) {
+        self.optional.push((path.to_string(), handle.clone_untyped()));
+    }
+}
+
+/// Where the player goes back to when restarting from the pause menu,
+/// the first bit of solid ground they stood on in the last level they entered
+#[derive(Resource)]
+pub struct Checkpoint {
+    pub level_iid: Option<String>,
+    pub level: LevelSelection,
+    pub translation: Vec2,
+}
+
+impl Default for Checkpoint {
+    fn default() -> Self {
+        Checkpoint {
+            level_iid: None,
+            level: LevelSelection::Index(0),
+            translation: Vec2::ZERO,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum Ability {
+    Dash,
+    WallJump,
+    WallSlide,
+    DoubleJump,
+}
+
+impl Ability {
+    /// Matches the values of the `Ability` enum in LDtk
+    pub fn from_identifier(identifier: &str) -> Option<Ability> {
+        match identifier {
+            "Dash" => Some(Ability::Dash),
+            "Wall_jump" => Some(Ability::WallJump),
+            "Wall_slide" => Some(Ability::WallSlide),
+            "Double_jump" => Some(Ability::DoubleJump),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ability::Dash => "Dash",
+            Ability::WallJump => "Wall Jump",
+            Ability::WallSlide => "Wall Slide",
+            Ability::DoubleJump => "Double Jump",
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Ability::Dash => "Press dash to burst forward",
+            Ability::WallJump => "Jump while sliding down a wall to leap off it",
+            Ability::WallSlide => "Hold towards a wall to slide down it",
+            Ability::DoubleJump => "Jump again in mid air",
+        }
+    }
+}
+
+/// The abilities the player has picked up so far.
+/// A new game starts with none, they all come from `Ability_pickup` entities
+#[derive(Resource, Clone, Default, Debug)]
+pub struct UnlockedAbilities {
+    pub abilities: HashSet<Ability>,
+}
+
+impl UnlockedAbilities {
+    pub fn has(&self, ability: Ability) -> bool {
+        self.abilities.contains(&ability)
+    }
+
+    /// Returns whether it wasn't unlocked already
+    pub fn unlock(&mut self, ability: Ability) -> bool {
+        self.abilities.insert(ability)
+    }
+
+    pub fn max_jump_count(&self) -> i32 {
+        if self.has(Ability::DoubleJump) { 2 } else { 1 }
+    }
+}
+
+/// Why the player can't control their character right now
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum InputLockReason {
+    AbilityPickup,
+    Dialogue,
+    Cutscene,
+}
+
+/// Takes control away from the player while anything is holding a lock, see [`PlayerInput`](crate::settings::PlayerInput)
+#[derive(Resource, Default)]
+pub struct InputLock {
+    pub reasons: HashSet<InputLockReason>,
+}
+
+impl InputLock {
+    pub fn lock(&mut self, reason: InputLockReason) {
+        self.reasons.insert(reason);
+    }
+
+    pub fn unlock(&mut self, reason: InputLockReason) {
+        self.reasons.remove(&reason);
+    }
+
+    pub fn is_locked(&self) -> bool {
+        !self.reasons.is_empty()
+    }
+}
+
+/// Actions held down on the player's behalf while their input is locked, so cutscenes can walk them around
+#[derive(Resource, Default)]
+pub struct ScriptedInput {
+    pub pressed: HashSet<InputAction>,
+}
+
+/// The banner shown after picking up an ability, the player can't move until it's gone
+#[derive(Resource)]
+pub struct AbilityCutscene {
+    pub ability: Option<Ability>,
+    pub timer: Timer,
+}
+
+impl Default for AbilityCutscene {
+    fn default() -> Self {
+        AbilityCutscene {
+            ability: None,
+            timer: Timer::from_seconds(2.5, TimerMode::Once),
+        }
+    }
+}
+
+/// Everything else the player has done that carries over between sessions, keyed by LDtk entity IIDs
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Progress {
+    pub collected_items: HashSet<String>,
+    pub defeated_bosses: HashSet<String>,
+    /// Set by dialogue lines and choices, see [`DialogueScript`]
+    pub dialogue_flags: HashSet<String>,
+    /// IIDs of the triggers whose cutscenes only play once
+    pub played_cutscenes: HashSet<String>,
+    pub play_time: f64,
+}
+
+/// The save slot the current game is being saved to
+#[derive(Resource, Default)]
+pub struct ActiveSave {
+    pub slot: Option<usize>,
+}
+
+/// The conversation being played, if any
+#[derive(Resource)]
+pub struct DialogueState {
+    pub script: Option<Handle<DialogueScript>>,
+    pub line: String,
+    /// How much of the line the typewriter has written out so far
+    pub shown_characters: f32,
+    pub characters_per_second: f32,
+    /// Index into the choices available with the current flags
+    pub selected_choice: usize,
+}
+
+impl Default for DialogueState {
+    fn default() -> Self {
+        DialogueState {
+            script: None,
+            line: String::new(),
+            shown_characters: 0.,
+            characters_per_second: 40.,
+            selected_choice: 0,
+        }
+    }
+}
+
+impl DialogueState {
+    pub fn is_active(&self) -> bool {
+        self.script.is_some()
+    }
+}
+
+/// The cutscene being played, if any, and how far through it is
+#[derive(Resource)]
+pub struct CutsceneState {
+    pub script: Option<Handle<CutsceneScript>>,
+    /// Center of the trigger that started the cutscene, which its positions are relative to
+    pub origin: Vec2,
+    /// The level the trigger is in, so spawned sprites go away with it
+    pub level: Option<Entity>,
+    pub step: usize,
+    pub is_step_started: bool,
+    pub step_timer: Timer,
+    pub dialogue: Option<Handle<DialogueScript>>,
+    pub is_dialogue_started: bool,
+    pub fade: f32,
+    pub fade_from: f32,
+    /// How long skip has been held down for
+    pub skip_seconds: f32,
+}
+
+impl Default for CutsceneState {
+    fn default() -> Self {
+        CutsceneState {
+            script: None,
+            origin: Vec2::ZERO,
+            level: None,
+            step: 0,
+            is_step_started: false,
+            step_timer: Timer::from_seconds(0., TimerMode::Once),
+            dialogue: None,
+            is_dialogue_started: false,
+            fade: 0.,
+            fade_from: 0.,
+            skip_seconds: 0.,
+        }
+    }
+}
+
+impl CutsceneState {
+    pub fn is_active(&self) -> bool {
+        self.script.is_some()
+    }
+}
+
+/// Freezes gameplay time for a moment to sell the impact of a hit
+#[derive(Resource)]
+pub struct HitStop {
+    pub is_stopped: bool,
+    pub timer: Timer,
+}
+
+impl Default for HitStop {
+    fn default() -> Self {
+        HitStop {
+            is_stopped: false,
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+        }
+    }
+}
+
+impl HitStop {
+    /// Never cuts short a longer hit stop that is already running
+    pub fn start(&mut self, seconds: f32) {
+        if self.is_stopped && self.timer.remaining_secs() >= seconds {
+            return;
+        }
+        self.is_stopped = true;
+        self.timer = Timer::from_seconds(seconds, TimerMode::Once);
+    }
+}
+
+/// Trauma based screen shake, the shake grows with the square of the trauma
+/// so small hits stay subtle while big ones really kick
+#[derive(Resource)]
+pub struct ScreenShake {
+    pub trauma: f32,
+    pub trauma_decay: f32,
+    pub max_offset: Vec2,
+    pub max_angle: f32,
+    pub frequency: f32,
+    pub elapsed: f32,
+    /// Currently applied on top of the camera's translation
+    pub offset: Vec2,
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        ScreenShake {
+            trauma: 0.0,
+            //per second
+            trauma_decay: 1.5,
+            max_offset: Vec2::new(12., 8.),
+            //radians
+            max_angle: 0.05,
+            frequency: 25.,
+            elapsed: 0.0,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl ScreenShake {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0.0, 1.0);
+    }
+
+    pub fn shake(&self) -> f32 {
+        self.trauma * self.trauma
+    }
+}
+
+/// Backdrops made of parallax layers, keyed by the name used in the `Backdrop` field of LDtk levels.
+/// Levels without one get the default backdrop
+#[derive(Resource)]
+pub struct BackdropRegistry {
+    pub backdrops: HashMap<String, Vec<ParallaxLayerConfig>>,
+    pub default_backdrop: String,
+}
+
+impl BackdropRegistry {
+    pub fn register(&mut self, name: &str, layers: Vec<ParallaxLayerConfig>) -> &mut Self {
+        self.backdrops.insert(name.to_string(), layers);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Vec<ParallaxLayerConfig>> {
+        self.backdrops.get(name)
+    }
+}
+
+impl Default for BackdropRegistry {
+    fn default() -> Self {
+        let mut registry = BackdropRegistry {
+            backdrops: HashMap::new(),
+            default_backdrop: "Testing".to_string(),
+        };
+
+        let testing_layer = |scroll_factor: f32, scale: f32, brightness: f32| ParallaxLayerConfig {
+            image: "atlas/Testing Background.png".to_string(),
+            scroll_factor: Vec2::splat(scroll_factor),
+            scale,
+            tile_x: true,
+            tile_y: true,
+            lock_vertical: false,
+            offset: Vec2::ZERO,
+            color: Color::rgb(brightness, brightness, brightness),
+        };
+
+        registry
+            .register("Testing", vec![
+                ParallaxLayerConfig {
+                    tile_y: false,
+                    lock_vertical: true,
+                    ..testing_layer(0.0, 24., 0.15)
+                },
+                testing_layer(0.2, 6., 0.25),
+                testing_layer(0.5, 3., 0.4),
+            ])
+            .register("None", Vec::new());
+
+        registry
+    }
+}
+
+/// Master, music and sound effect volumes, from 0 to 1
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VolumeSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        VolumeSettings {
+            master: 1.0,
+            music: 0.8,
+            sfx: 1.0,
+        }
+    }
+}
+
+impl VolumeSettings {
+    /// Pulls volumes from a hand edited settings file back between 0 and 1
+    pub fn clamp(&mut self) {
+        for volume in [&mut self.master, &mut self.music, &mut self.sfx] {
+            *volume = if volume.is_nan() { 0. } else { volume.clamp(0., 1.) };
+        }
+    }
+
+    pub fn channel_mut(&mut self, channel: VolumeChannel) -> &mut f32 {
+        match channel {
+            VolumeChannel::Master => &mut self.master,
+            VolumeChannel::Music => &mut self.music,
+            VolumeChannel::Sfx => &mut self.sfx,
+        }
+    }
+
+    pub fn channel(&self, channel: VolumeChannel) -> f32 {
+        match channel {
+            VolumeChannel::Master => self.master,
+            VolumeChannel::Music => self.music,
+            VolumeChannel::Sfx => self.sfx,
+        }
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        self.master * self.music
+    }
+
+    pub fn sfx_volume(&self) -> f32 {
+        self.master * self.sfx
+    }
+}
+
+/// The action waiting for a key press on the key bindings screen, menus ignore the keyboard until it gets one
+#[derive(Resource, Default)]
+pub struct KeyRebinding {
+    pub action: Option<InputAction>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MusicTrack {
+    pub path: String,
+    pub volume: f32,
+}
+
+/// Every music track, keyed by the name used in the `Music` field of LDtk levels.
+/// It starts out empty until the music assets exist, the levels play `Chesslands` and the King plays `Boss`
+#[derive(Resource, Default)]
+pub struct MusicRegistry {
+    pub tracks: HashMap<String, MusicTrack>,
+}
+
+impl MusicRegistry {
+    // unused until the first music asset is added
+    #[allow(dead_code)]
+    pub fn register(&mut self, name: &str, track: MusicTrack) -> &mut Self {
+        self.tracks.insert(name.to_string(), track);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MusicTrack> {
+        self.tracks.get(name)
+    }
+}
+
+/// Which track should be playing. A boss track takes over from the level's track,
+/// and everything gets quieter while ducked
+#[derive(Resource)]
+pub struct MusicManager {
+    pub level_track: Option<String>,
+    pub boss_track: Option<String>,
+    pub crossfade_seconds: f32,
+    pub is_ducked: bool,
+    pub duck_volume: f32,
+}
+
+impl Default for MusicManager {
+    fn default() -> Self {
+        MusicManager {
+            level_track: None,
+            boss_track: None,
+            crossfade_seconds: 1.5,
+            is_ducked: false,
+            duck_volume: 0.3,
+        }
+    }
+}
+
+impl MusicManager {
+    pub fn current_track(&self) -> Option<&String> {
+        self.boss_track.as_ref().or(self.level_track.as_ref())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SoundEffect {
+    pub path: String,
+    pub volume: f32,
+    /// How far the pitch can randomly stray from normal, so repeated sounds don't grate
+    pub pitch_variance: f32,
+    pub looping: bool,
+}
+
+/// Every sound effect, keyed by the name the audio systems play it by.
+/// It starts out empty until the sound effect assets exist, the audio systems play
+/// `Walk`, `Run`, `Jump`, `WallJump`, `Land`, `Dash`, `WallSlide`, `Hurt` and `Collect`
+#[derive(Resource, Default)]
+pub struct SoundRegistry {
+    pub sounds: HashMap<String, SoundEffect>,
+}
+
+impl SoundRegistry {
+    // unused until the first sound effect asset is added
+    #[allow(dead_code)]
+    pub fn register(&mut self, name: &str, sound: SoundEffect) -> &mut Self {
+        self.sounds.insert(name.to_string(), sound);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SoundEffect> {
+        self.sounds.get(name)
+    }
+}
+
+/// Every kind of enemy, keyed by the name used in LDtk.
+/// Chess pieces use their piece name, other LDtk entities can pick one with their `Behaviour` field
+#[derive(Resource)]
+pub struct BehaviourRegistry {
+    pub configs: HashMap<String, BehaviourConfig>,
+}
+
+impl BehaviourRegistry {
+    pub fn register(&mut self, name: &str, config: BehaviourConfig) -> &mut Self {
+        self.configs.insert(name.to_string(), config);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BehaviourConfig> {
+        self.configs.get(name)
+    }
+
+    /// The config registered under the name, with any overrides set on the LDtk entity
+    pub fn config_for(&self, name: &str, entity_instance: &EntityInstance) -> Option<BehaviourConfig> {
+        self.get(name).map(|config| config.clone().with_entity_fields(entity_instance))
+    }
+}
+
+impl Default for BehaviourRegistry {
+    fn default() -> Self {
+        let mut registry = BehaviourRegistry {
+            configs: HashMap::new(),
+        };
+
+        let lunge = AttackBehaviour {
+            range: 40.,
+            windup_seconds: 0.4,
+            lunge_seconds: 0.2,
+            lunge_speed: 20000.,
+            slam_trauma: 0.0,
+        };
+
+        registry
+            .register("Pawn", BehaviourConfig {
+                patrol: Some(PatrolBehaviour { speed: 2000., distance: None }),
+                ..default()
+            })
+            .register("Rook", BehaviourConfig {
+                patrol: Some(PatrolBehaviour { speed: 3000., distance: Some(96.) }),
+                chase: Some(ChaseBehaviour { speed: 8000., give_up_seconds: 1.5 }),
+                attack: Some(lunge.clone()),
+                ..default()
+            })
+            .register("Knight", BehaviourConfig {
+                patrol: Some(PatrolBehaviour { speed: 3000., distance: Some(64.) }),
+                chase: Some(ChaseBehaviour { speed: 7000., give_up_seconds: 1.5 }),
+                attack: Some(lunge.clone()),
+                ..default()
+            })
+            .register("Bishop", BehaviourConfig {
+                patrol: Some(PatrolBehaviour { speed: 3000., distance: Some(64.) }),
+                chase: Some(ChaseBehaviour { speed: 7000., give_up_seconds: 1.5 }),
+                attack: Some(lunge.clone()),
+                ..default()
+            })
+            .register("Queen", BehaviourConfig {
+                chase: Some(ChaseBehaviour { speed: 9000., give_up_seconds: 3. }),
+                attack: Some(lunge),
+                aggro_radius: 400.,
+                ..default()
+            })
+            .register("King", BehaviourConfig {
+                chase: Some(ChaseBehaviour { speed: 4000., give_up_seconds: 3. }),
+                attack: Some(AttackBehaviour {
+                    range: 48.,
+                    windup_seconds: 0.8,
+                    lunge_seconds: 0.3,
+                    lunge_speed: 12000.,
+                    slam_trauma: 0.6,
+                }),
+                aggro_radius: 300.,
+                boss_music: Some("Boss".to_string()),
+                is_boss: true,
+                ..default()
+            });
+
+        registry
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum DoorTransitionPhase {
+    #[default]
+    Idle,
+    FadingOut,
+    Loading,
+    FadingIn,
+}
+
+/// A warp through a door that is in progress
+#[derive(Resource)]
+pub struct DoorTransition {
+    pub phase: DoorTransitionPhase,
+    pub target_level: Option<LevelSelection>,
+    pub target_door_iid: Option<String>,
+    pub fade_seconds: f32,
+    pub timer: Timer,
+}
+
+impl Default for DoorTransition {
+    fn default() -> Self {
+        DoorTransition {
+            phase: DoorTransitionPhase::Idle,
+            target_level: None,
+            target_door_iid: None,
+            fade_seconds: 0.3,
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+        }
+    }
+}
+
+impl DoorTransition {
+    pub fn is_active(&self) -> bool {
+        self.phase != DoorTransitionPhase::Idle
+    }
+
+    pub fn set_phase(&mut self, phase: DoorTransitionPhase, seconds: f32) {
+        self.phase = phase;
+        self.timer = Timer::from_seconds(seconds, TimerMode::Once);
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::Ability;
+use crate::storage::{self, StorageError};
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Bumped whenever the save format changes in a way `#[serde(default)]` can't cover,
+/// along with a new step in [`SaveData::migrate`]
+pub const SAVE_VERSION: u32 = 1;
+pub const SAVE_SLOTS: usize = 3;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedCheckpoint {
+    pub level_iid: String,
+    pub translation: Vec2,
+}
+
+/// Everything written to a save slot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    /// Only shown on the save slot picker
+    pub level_name: String,
+    pub checkpoint: Option<SavedCheckpoint>,
+    pub unlocked_abilities: HashSet<Ability>,
+    pub collected_items: HashSet<String>,
+    pub defeated_bosses: HashSet<String>,
+    pub dialogue_flags: HashSet<String>,
+    pub played_cutscenes: HashSet<String>,
+    pub play_time: f64,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        SaveData {
+            version: SAVE_VERSION,
+            level_name: String::new(),
+            checkpoint: None,
+            unlocked_abilities: HashSet::new(),
+            collected_items: HashSet::new(),
+            defeated_bosses: HashSet::new(),
+            dialogue_flags: HashSet::new(),
+            played_cutscenes: HashSet::new(),
+            play_time: 0.,
+        }
+    }
+}
+
+/// Just enough of a save to know how to read the rest of it
+#[derive(Deserialize)]
+struct SaveHeader {
+    #[serde(default)]
+    version: u32,
+}
+
+impl SaveData {
+    pub fn path(slot: usize) -> Result<PathBuf, StorageError> {
+        Ok(storage::data_folder()?.join("saves").join(format!("slot_{}.ron", slot + 1)))
+    }
+
+    /// The save in a slot, `None` if nothing has been saved there yet
+    pub fn load(slot: usize) -> Result<Option<SaveData>, StorageError> {
+        match storage::read_file(&Self::path(slot)?)? {
+            Some(contents) => Self::parse(&contents).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn save(&self, slot: usize) -> Result<(), StorageError> {
+        storage::write_ron(&Self::path(slot)?, self)
+    }
+
+    /// Reads the version first, so saves from older versions are migrated up a version at a time
+    /// and saves from newer versions are refused rather than misread
+    fn parse(contents: &str) -> Result<SaveData, StorageError> {
+        let header: SaveHeader = ron::from_str(contents).map_err(StorageError::Parse)?;
+        if header.version > SAVE_VERSION {
+            return Err(StorageError::NewerVersion(header.version));
+        }
+
+        let mut save_data: SaveData = ron::from_str(contents).map_err(StorageError::Parse)?;
+        for version in header.version..SAVE_VERSION {
+            save_data = Self::migrate(save_data, version);
+        }
+        Ok(save_data)
+    }
+
+    /// Upgrades a save written with the given version to the one after it
+    fn migrate(mut save_data: SaveData, version: u32) -> SaveData {
+        match version {
+            // saves from before the version field was added have the same layout, `#[serde(default)]` fills in the rest
+            0 => save_data.version = 1,
+            _ => unreachable!("no migration from save version {}", version),
+        }
+        save_data
+    }
+
+    /// Label for the save slot picker
+    pub fn summary(&self) -> String {
+        let minutes = (self.play_time / 60.) as u32;
+        let level_name = if self.level_name.is_empty() { "Start" } else { &self.level_name };
+        format!("{} {}:{:02}", level_name, minutes / 60, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save_data() -> SaveData {
+        SaveData {
+            level_name: "Test_chambers".to_string(),
+            checkpoint: Some(SavedCheckpoint {
+                level_iid: "level".to_string(),
+                translation: Vec2::new(56., 592.),
+            }),
+            unlocked_abilities: HashSet::from([Ability::Dash, Ability::WallJump]),
+            collected_items: HashSet::from(["shard".to_string()]),
+            dialogue_flags: HashSet::from(["met_white_king".to_string()]),
+            play_time: 125.5,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let contents = ron::ser::to_string_pretty(&save_data(), ron::ser::PrettyConfig::default()).unwrap();
+        let parsed = SaveData::parse(&contents).unwrap();
+
+        assert_eq!(parsed.version, SAVE_VERSION);
+        assert_eq!(parsed.level_name, "Test_chambers");
+        assert_eq!(parsed.checkpoint.map(|checkpoint| checkpoint.translation), Some(Vec2::new(56., 592.)));
+        assert_eq!(parsed.unlocked_abilities, save_data().unlocked_abilities);
+        assert_eq!(parsed.collected_items, save_data().collected_items);
+        assert_eq!(parsed.dialogue_flags, save_data().dialogue_flags);
+        assert_eq!(parsed.play_time, 125.5);
+    }
+
+    #[test]
+    fn save_without_a_version_is_migrated() {
+        let parsed = SaveData::parse(r#"(level_name: "The_Playground", unlocked_abilities: [Dash])"#).unwrap();
+
+        assert_eq!(parsed.version, SAVE_VERSION);
+        assert_eq!(parsed.level_name, "The_Playground");
+        assert_eq!(parsed.unlocked_abilities, HashSet::from([Ability::Dash]));
+    }
+
+    #[test]
+    fn save_from_a_newer_version_is_refused() {
+        let contents = format!("(version: {}, level_name: \"Somewhere new\")", SAVE_VERSION + 1);
+
+        assert!(matches!(SaveData::parse(&contents), Err(StorageError::NewerVersion(version)) if version == SAVE_VERSION + 1));
+    }
+
+    #[test]
+    fn broken_save_is_a_parse_error() {
+        assert!(matches!(SaveData::parse("(version: 1, play_time: \"soon\")"), Err(StorageError::Parse(_))));
+    }
+}
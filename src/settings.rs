@@ -0,0 +1,219 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::{PresentMode, WindowLevel, WindowMode, WindowResolution};
+use serde::{Deserialize, Serialize};
+
+use crate::resources::{InputLock, ScriptedInput, VolumeSettings};
+use crate::storage::{self, StorageError};
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Size of the window at a scale of 1
+pub const BASE_WIDTH: f32 = 640.;
+pub const BASE_HEIGHT: f32 = 480.;
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Window size as a multiple of 640×480, the view of the world stays the same
+    pub scale: f32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub always_on_top: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            scale: 1.0,
+            fullscreen: false,
+            vsync: true,
+            always_on_top: true,
+        }
+    }
+}
+
+impl DisplaySettings {
+    pub const SCALES: [f32; 4] = [1.0, 1.5, 2.0, 3.0];
+
+    /// Steps through the available scales, staying within them
+    pub fn step_scale(&mut self, step: i32) {
+        let index = Self::SCALES
+            .iter()
+            .position(|scale| *scale >= self.scale)
+            .unwrap_or(0) as i32;
+        self.scale = Self::SCALES[(index + step).clamp(0, Self::SCALES.len() as i32 - 1) as usize];
+    }
+
+    /// Snaps a scale from a hand edited settings file to the closest one available,
+    /// since the camera divides by it
+    pub fn snap_scale(&mut self) {
+        let scale = if self.scale.is_finite() { self.scale } else { Self::SCALES[0] };
+        self.scale = Self::SCALES
+            .into_iter()
+            .min_by(|a, b| (a - scale).abs().total_cmp(&(b - scale).abs()))
+            .unwrap_or(Self::SCALES[0]);
+    }
+
+    pub fn resolution(&self) -> WindowResolution {
+        (BASE_WIDTH * self.scale, BASE_HEIGHT * self.scale).into()
+    }
+
+    pub fn apply(&self, window: &mut Window) {
+        window.resolution = self.resolution();
+        window.mode = if self.fullscreen { WindowMode::BorderlessFullscreen } else { WindowMode::Windowed };
+        window.present_mode = if self.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync };
+        window.window_level = if self.always_on_top { WindowLevel::AlwaysOnTop } else { WindowLevel::Normal };
+    }
+}
+
+/// Everything the player can do with a key
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum InputAction {
+    Left,
+    Right,
+    Up,
+    Down,
+    Jump,
+    Run,
+    Dash,
+    Attack,
+    Pause,
+    Skip,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 10] = [
+        InputAction::Left,
+        InputAction::Right,
+        InputAction::Up,
+        InputAction::Down,
+        InputAction::Jump,
+        InputAction::Run,
+        InputAction::Dash,
+        InputAction::Attack,
+        InputAction::Pause,
+        InputAction::Skip,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputAction::Left => "Left",
+            InputAction::Right => "Right",
+            InputAction::Up => "Up",
+            InputAction::Down => "Down",
+            InputAction::Jump => "Jump",
+            InputAction::Run => "Run",
+            InputAction::Dash => "Dash",
+            InputAction::Attack => "Attack",
+            InputAction::Pause => "Pause",
+            InputAction::Skip => "Skip",
+        }
+    }
+}
+
+/// The keys bound to each action, any of them will do
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub bindings: HashMap<InputAction, Vec<KeyCode>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            bindings: HashMap::from([
+                (InputAction::Left, vec![KeyCode::Left]),
+                (InputAction::Right, vec![KeyCode::Right]),
+                (InputAction::Up, vec![KeyCode::Up]),
+                (InputAction::Down, vec![KeyCode::Down]),
+                (InputAction::Jump, vec![KeyCode::Space]),
+                (InputAction::Run, vec![KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+                (InputAction::Dash, vec![KeyCode::ControlLeft]),
+                (InputAction::Attack, vec![KeyCode::X]),
+                (InputAction::Pause, vec![KeyCode::Escape]),
+                (InputAction::Skip, vec![KeyCode::Return]),
+            ]),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    pub fn pressed(&self, input: &Input<KeyCode>, action: InputAction) -> bool {
+        input.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, input: &Input<KeyCode>, action: InputAction) -> bool {
+        input.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    /// Replaces whatever was bound to the action
+    pub fn bind(&mut self, action: InputAction, key: KeyCode) {
+        self.bindings.insert(action, vec![key]);
+    }
+
+    /// Gives actions added since the settings were saved their default keys
+    pub fn add_missing(&mut self) {
+        for (action, keys) in KeyBindings::default().bindings {
+            self.bindings.entry(action).or_insert(keys);
+        }
+    }
+}
+
+/// The keyboard as gameplay sees it. While something has taken control away from the player,
+/// only the actions it holds down through [`ScriptedInput`] are pressed
+#[derive(SystemParam)]
+pub struct PlayerInput<'w> {
+    input: Res<'w, Input<KeyCode>>,
+    key_bindings: Res<'w, KeyBindings>,
+    input_lock: Res<'w, InputLock>,
+    scripted_input: Res<'w, ScriptedInput>,
+}
+
+impl PlayerInput<'_> {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        if self.input_lock.is_locked() {
+            self.scripted_input.pressed.contains(&action)
+        } else {
+            self.key_bindings.pressed(&self.input, action)
+        }
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        !self.input_lock.is_locked() && self.key_bindings.just_pressed(&self.input, action)
+    }
+}
+
+/// Everything in the settings file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub display: DisplaySettings,
+    pub volume: VolumeSettings,
+    pub key_bindings: KeyBindings,
+}
+
+impl Settings {
+    pub fn path() -> Result<PathBuf, StorageError> {
+        Ok(storage::config_folder()?.join("settings.ron"))
+    }
+
+    /// The saved settings, or the defaults on the first run.
+    /// Values the menus could never have picked are brought back into range
+    pub fn load() -> Result<Settings, StorageError> {
+        let mut settings: Settings = storage::read_ron(&Self::path()?)?.unwrap_or_default();
+        settings.display.snap_scale();
+        settings.volume.clamp();
+        settings.key_bindings.add_missing();
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> Result<(), StorageError> {
+        storage::write_ron(&Self::path()?, self)
+    }
+}
@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Hash, States)]
+pub enum GameState {
+    #[default]
+    Loading,
+    MainMenu,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// Systems that only run while the game is being played,
+/// the set is configured once in `main` to run in [`GameState::Playing`]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash, SystemSet)]
+pub struct GameplaySet;
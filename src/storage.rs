@@ -0,0 +1,96 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the folder everything is stored in, inside the platform's config and data folders
+const APP_FOLDER: &str = "EnterTheDreamer";
+
+#[derive(Debug)]
+pub enum StorageError {
+    NoHomeFolder,
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// Written by a newer version of the game, which this one can't know how to read
+    NewerVersion(u32),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::NoHomeFolder => write!(f, "couldn't find the home folder"),
+            StorageError::Io(error) => write!(f, "{}", error),
+            StorageError::Parse(error) => write!(f, "{}", error),
+            StorageError::Serialize(error) => write!(f, "{}", error),
+            StorageError::NewerVersion(version) => write!(f, "written by a newer version of the game (version {})", version),
+        }
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(error: io::Error) -> StorageError {
+        StorageError::Io(error)
+    }
+}
+
+fn home_folder() -> Option<PathBuf> {
+    std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from)
+}
+
+/// `%APPDATA%` on Windows, `~/Library/Application Support` on macOS and `$XDG_CONFIG_HOME` or `~/.config` everywhere else
+pub fn config_folder() -> Result<PathBuf, StorageError> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_folder().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).or_else(|| home_folder().map(|home| home.join(".config")))
+    };
+    base.map(|base| base.join(APP_FOLDER)).ok_or(StorageError::NoHomeFolder)
+}
+
+/// `%APPDATA%` on Windows, `~/Library/Application Support` on macOS and `$XDG_DATA_HOME` or `~/.local/share` everywhere else
+pub fn data_folder() -> Result<PathBuf, StorageError> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_folder().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME").map(PathBuf::from).or_else(|| home_folder().map(|home| home.join(".local/share")))
+    };
+    base.map(|base| base.join(APP_FOLDER)).ok_or(StorageError::NoHomeFolder)
+}
+
+/// Reads a whole file, `None` if it doesn't exist yet
+pub fn read_file(path: &Path) -> Result<Option<String>, StorageError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Reads a RON file, `None` if it doesn't exist yet
+pub fn read_ron<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, StorageError> {
+    match read_file(path)? {
+        Some(contents) => ron::from_str(&contents).map(Some).map_err(StorageError::Parse),
+        None => Ok(None),
+    }
+}
+
+/// Writes to a temporary file first, so a crash halfway through can't leave a broken file behind
+pub fn write_ron<T: Serialize>(path: &Path, value: &T) -> Result<(), StorageError> {
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).map_err(StorageError::Serialize)?;
+
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, contents)?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
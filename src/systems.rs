@@ -1,4 +1,27 @@
 pub mod setup;
 pub mod camera;
 pub mod detection;
-pub mod movement;
\ No newline at end of file
+pub mod movement;
+pub mod enemy;
+pub mod chessboard;
+pub mod health;
+pub mod combat;
+pub mod behaviour;
+pub mod navigation;
+pub mod level;
+pub mod door;
+pub mod effects;
+pub mod parallax;
+pub mod animation;
+pub mod audio;
+pub mod music;
+pub mod game_state;
+pub mod menu;
+pub mod loading;
+pub mod settings;
+pub mod save;
+pub mod ability;
+pub mod hud;
+pub mod collectible;
+pub mod dialogue;
+pub mod cutscene;
\ No newline at end of file
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+
+fn spawn_ability_banner(commands: &mut Commands, ability: Ability) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    top: Val::Percent(20.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(12.)),
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                z_index: ZIndex::Global(40),
+                ..default()
+            },
+            AbilityBanner,
+        ))
+        .with_children(|banner| {
+            banner.spawn(TextBundle::from_section(ability.name(), TextStyle {
+                font_size: 32.,
+                color: Color::WHITE,
+                ..default()
+            }));
+            banner.spawn(TextBundle::from_section(ability.hint(), TextStyle {
+                font_size: 16.,
+                color: Color::rgb(0.8, 0.8, 0.8),
+                ..default()
+            }));
+        });
+}
+
+/// Touching a pickup unlocks its ability and shows it off, holding the player still until the banner goes away
+pub fn collect_ability_pickups(
+    mut commands: Commands,
+    mut unlocked_abilities: ResMut<UnlockedAbilities>,
+    mut ability_cutscene: ResMut<AbilityCutscene>,
+    mut input_lock: ResMut<InputLock>,
+    mut screen_shake_events: EventWriter<ScreenShakeEvent>,
+    pickup_query: Query<(Entity, &AbilityPickup, &GlobalTransform)>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return };
+    let player_translation = player_transform.translation().truncate();
+
+    for (entity, ability_pickup, pickup_transform) in &pickup_query {
+        let offset = (player_translation - pickup_transform.translation().truncate()).abs();
+        if offset.x > ability_pickup.half_extents.x || offset.y > ability_pickup.half_extents.y {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+
+        let Some(ability) = ability_pickup.ability else {
+            warn!("Ability pickup has no ability");
+            continue;
+        };
+        // only one banner at a time, picking up two abilities at once would be odd anyway
+        if !unlocked_abilities.unlock(ability) || ability_cutscene.ability.is_some() {
+            continue;
+        }
+
+        ability_cutscene.ability = Some(ability);
+        ability_cutscene.timer.reset();
+        input_lock.lock(InputLockReason::AbilityPickup);
+        screen_shake_events.send(ScreenShakeEvent { trauma: 0.3 });
+        spawn_ability_banner(&mut commands, ability);
+    }
+}
+
+/// Pickups for abilities the player already has don't come back when their level is loaded again
+pub fn despawn_unlocked_pickups(
+    mut commands: Commands,
+    unlocked_abilities: Res<UnlockedAbilities>,
+    query: Query<(Entity, &AbilityPickup), Added<AbilityPickup>>,
+) {
+    for (entity, ability_pickup) in &query {
+        if ability_pickup.ability.is_some_and(|ability| unlocked_abilities.has(ability)) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn ability_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ability_cutscene: ResMut<AbilityCutscene>,
+    mut input_lock: ResMut<InputLock>,
+    banner_query: Query<Entity, With<AbilityBanner>>,
+) {
+    if ability_cutscene.ability.is_none() {
+        return;
+    }
+
+    ability_cutscene.timer.tick(time.delta());
+    if ability_cutscene.timer.finished() {
+        ability_cutscene.ability = None;
+        input_lock.unlock(InputLockReason::AbilityPickup);
+        for entity in &banner_query {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
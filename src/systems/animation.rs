@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::animation::*;
+use crate::components::*;
+use crate::resources::*;
+
+pub fn load_player_animations(
+    game_assets: Res<GameAssets>,
+    mut query: Query<&mut Animator, Added<Player>>,
+) {
+    for mut animator in &mut query {
+        animator.sheet = game_assets.player_animations.clone();
+    }
+}
+
+/// Gives animated sprites their texture atlas once the animation sheet has loaded
+pub fn build_texture_atlases(
+    asset_server: Res<AssetServer>,
+    animation_sheets: Res<Assets<AnimationSheet>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut query: Query<(&Animator, &mut Handle<TextureAtlas>)>,
+) {
+    for (animator, mut texture_atlas_handle) in &mut query {
+        if *texture_atlas_handle != Handle::default() {
+            continue;
+        }
+        let Some(animation_sheet) = animation_sheets.get(&animator.sheet) else { continue };
+
+        let texture_atlas = animation_sheet.texture_atlas(asset_server.load(animation_sheet.texture.as_str()));
+        *texture_atlas_handle = texture_atlases.add(texture_atlas);
+    }
+}
+
+/// Picks the player's clip from what it is doing, the most specific action winning
+pub fn player_animation_state(
+    mut query: Query<(
+        &mut Animator,
+        &mut TextureAtlasSprite,
+        &HorizontalMover,
+        &VerticalMover,
+        &GroundDetection,
+        &Velocity,
+        &Attacker,
+    ), With<Player>>,
+) {
+    for (mut animator, mut sprite, horizontal_mover, vertical_mover, ground_detection, velocity, attacker) in &mut query {
+        let scripted_clip = animator.scripted_clip.clone();
+        let clip = if let Some(scripted_clip) = &scripted_clip {
+            scripted_clip
+        } else if attacker.is_attacking {
+            "Attack"
+        } else if horizontal_mover.is_dashing {
+            "Dash"
+        } else if vertical_mover.is_wall_jumping {
+            "WallJump"
+        } else if vertical_mover.is_wall_sliding {
+            "WallSlide"
+        } else if !ground_detection.on_ground {
+            if velocity.linvel.y > 0. { "Jump" } else { "Fall" }
+        } else if horizontal_mover.is_horizontal_moving {
+            if horizontal_mover.current_speed.abs() > horizontal_mover.walk_speed { "Run" } else { "Walk" }
+        } else {
+            "Idle"
+        };
+        animator.play(clip);
+
+        // the graphics face right
+        sprite.flip_x = horizontal_mover.facing_direction == FacingDirection::Left;
+    }
+}
+
+/// Steps through the frames of each animator's clip, holding the last frame of clips that don't loop
+pub fn animate_sprites(
+    time: Res<Time>,
+    animation_sheets: Res<Assets<AnimationSheet>>,
+    mut query: Query<(&mut Animator, &mut TextureAtlasSprite)>,
+) {
+    for (mut animator, mut sprite) in &mut query {
+        let Some(animation_sheet) = animation_sheets.get(&animator.sheet) else { continue };
+        let Some(clip) = animation_sheet.clips.get(&animator.clip).or_else(|| animation_sheet.clips.get("Idle")) else { continue };
+
+        animator.frame_timer.set_duration(std::time::Duration::from_secs_f32(1. / clip.fps));
+        animator.frame_timer.tick(time.delta());
+
+        for _ in 0..animator.frame_timer.times_finished_this_tick() {
+            if animator.frame + 1 < clip.frame_count() {
+                animator.frame += 1;
+            } else if clip.looping {
+                animator.frame = 0;
+            } else {
+                animator.is_finished = true;
+            }
+        }
+
+        sprite.index = clip.first + animator.frame.min(clip.frame_count() - 1);
+    }
+}
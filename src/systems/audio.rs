@@ -0,0 +1,130 @@
+use bevy::audio::{PlaybackMode, Volume};
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+
+/// Landings this fast or faster play at full volume
+const LANDING_FULL_VOLUME_SPEED: f32 = 900.;
+
+fn sound_bundle(asset_server: &AssetServer, sound: &SoundEffect, volume: f32) -> AudioBundle {
+    // volume already takes the sfx and master volumes into account
+    let pitch = 1. + (fastrand::f32() * 2. - 1.) * sound.pitch_variance;
+
+    AudioBundle {
+        source: asset_server.load(sound.path.as_str()),
+        settings: PlaybackSettings {
+            mode: if sound.looping { PlaybackMode::Loop } else { PlaybackMode::Despawn },
+            volume: Volume::new_relative(sound.volume * volume),
+            speed: pitch,
+            paused: false,
+        },
+    }
+}
+
+/// Sounds that haven't been registered yet are skipped, like looping ones
+fn play_sound(commands: &mut Commands, asset_server: &AssetServer, sound_registry: &SoundRegistry, volume_settings: &VolumeSettings, name: &str, volume: f32) {
+    if let Some(sound) = sound_registry.get(name) {
+        commands.spawn(sound_bundle(asset_server, sound, volume * volume_settings.sfx_volume()));
+    }
+}
+
+fn start_looping_sound(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    sound_registry: &SoundRegistry,
+    volume_settings: &VolumeSettings,
+    looping_query: &Query<(Entity, &LoopingSound)>,
+    name: &str,
+) {
+    if looping_query.iter().any(|(_, looping_sound)| looping_sound.name == name) {
+        return;
+    }
+    if let Some(sound) = sound_registry.get(name) {
+        commands.spawn((
+            sound_bundle(asset_server, sound, volume_settings.sfx_volume()),
+            LoopingSound { name: name.to_string() },
+        ));
+    }
+}
+
+fn stop_looping_sound(commands: &mut Commands, looping_query: &Query<(Entity, &LoopingSound)>, name: &str) {
+    for (entity, looping_sound) in looping_query {
+        if looping_sound.name == name {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn player_action_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sound_registry: Res<SoundRegistry>,
+    volume_settings: Res<VolumeSettings>,
+    mut player_events: EventReader<PlayerEvent>,
+    looping_query: Query<(Entity, &LoopingSound)>,
+) {
+    for player_event in player_events.iter() {
+        match *player_event {
+            PlayerEvent::Jumped => play_sound(&mut commands, &asset_server, &sound_registry, &volume_settings, "Jump", 1.),
+            PlayerEvent::WallJumped => play_sound(&mut commands, &asset_server, &sound_registry, &volume_settings, "WallJump", 1.),
+            PlayerEvent::Landed { impact_speed } => {
+                let volume = (impact_speed / LANDING_FULL_VOLUME_SPEED).clamp(0.2, 1.);
+                play_sound(&mut commands, &asset_server, &sound_registry, &volume_settings, "Land", volume);
+            }
+            PlayerEvent::Dashed => play_sound(&mut commands, &asset_server, &sound_registry, &volume_settings, "Dash", 1.),
+            PlayerEvent::Hurt => play_sound(&mut commands, &asset_server, &sound_registry, &volume_settings, "Hurt", 1.),
+            PlayerEvent::Collected => play_sound(&mut commands, &asset_server, &sound_registry, &volume_settings, "Collect", 1.),
+            PlayerEvent::WallSlideStarted => {
+                start_looping_sound(&mut commands, &asset_server, &sound_registry, &volume_settings, &looping_query, "WallSlide");
+            }
+            PlayerEvent::WallSlideStopped => stop_looping_sound(&mut commands, &looping_query, "WallSlide"),
+        }
+    }
+}
+
+/// Loops walking or running footsteps for as long as the player moves along the ground
+pub fn footsteps(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sound_registry: Res<SoundRegistry>,
+    volume_settings: Res<VolumeSettings>,
+    player_query: Query<(&HorizontalMover, &GroundDetection), With<Player>>,
+    looping_query: Query<(Entity, &LoopingSound)>,
+) {
+    let Ok((horizontal_mover, ground_detection)) = player_query.get_single() else { return };
+
+    let footstep = if !ground_detection.on_ground || !horizontal_mover.is_horizontal_moving || horizontal_mover.is_dashing {
+        None
+    } else if horizontal_mover.current_speed.abs() > horizontal_mover.walk_speed {
+        Some("Run")
+    } else {
+        Some("Walk")
+    };
+
+    for name in ["Walk", "Run"] {
+        if footstep == Some(name) {
+            start_looping_sound(&mut commands, &asset_server, &sound_registry, &volume_settings, &looping_query, name);
+        } else {
+            stop_looping_sound(&mut commands, &looping_query, name);
+        }
+    }
+}
+
+/// Sounds that are already looping pick up volume changes from the settings
+pub fn looping_sound_volume(
+    sound_registry: Res<SoundRegistry>,
+    volume_settings: Res<VolumeSettings>,
+    looping_query: Query<(&LoopingSound, &AudioSink)>,
+) {
+    if !volume_settings.is_changed() {
+        return;
+    }
+
+    for (looping_sound, audio_sink) in &looping_query {
+        if let Some(sound) = sound_registry.get(&looping_sound.name) {
+            audio_sink.set_volume(sound.volume * volume_settings.sfx_volume());
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+
+use std::collections::HashSet;
+
+/// Gives newly spawned enemies the behaviour registered for them, or the default one.
+/// Board pieces are left alone since they follow the rules of chess instead
+pub fn configure_behaviours(
+    mut commands: Commands,
+    behaviour_registry: Res<BehaviourRegistry>,
+    query: Query<(Entity, &EntityInstance, &ChessPiece, &Transform), (Added<Enemy>, Without<BoardPiece>)>,
+) {
+    for (entity, entity_instance, chess_piece, transform) in &query {
+        let name = match entity_instance.get_maybe_string_field("Behaviour") {
+            Ok(Some(name)) => name.as_str(),
+            _ => chess_piece.identifier(),
+        };
+
+        // an enemy still has to be able to die, even if its behaviour is misspelled in LDtk
+        let config = behaviour_registry.config_for(name, entity_instance).unwrap_or_else(|| {
+            warn!("No behaviour registered for \"{}\", using the default", name);
+            BehaviourConfig::default().with_entity_fields(entity_instance)
+        });
+
+        let facing_direction = match entity_instance.get_maybe_enum_field("Direction") {
+            Ok(Some(direction)) if direction == "Right" => FacingDirection::Right,
+            _ => FacingDirection::Left,
+        };
+
+        commands.entity(entity).insert((
+            Behaviour::new(
+                config,
+                facing_direction,
+                transform.translation.truncate(),
+            ),
+            Perception::default(),
+            Navigator::default(),
+        ));
+    }
+}
+
+/// An enemy sees the player when they are within its aggro radius
+/// and nothing solid is in the way
+pub fn perception(
+    rapier_context: Res<RapierContext>,
+    player_query: Query<(Entity, &GlobalTransform), With<Player>>,
+    mut query: Query<(Entity, &GlobalTransform, &Behaviour, &mut Perception)>,
+) {
+    let player = player_query.get_single().ok();
+
+    for (entity, transform, behaviour, mut perception) in &mut query {
+        let Some((player_entity, player_transform)) = player else {
+            perception.sees_player = false;
+            continue;
+        };
+
+        let origin = transform.translation().truncate();
+        perception.player_offset = player_transform.translation().truncate() - origin;
+
+        let distance = perception.player_offset.length();
+        perception.sees_player = distance > 0. && distance <= behaviour.config.aggro_radius && {
+            let filter = QueryFilter::new().exclude_rigid_body(entity).exclude_sensors();
+            matches!(
+                rapier_context.cast_ray(origin, perception.player_offset / distance, distance, true, filter),
+                Some((hit_entity, _)) if hit_entity == player_entity
+            )
+        };
+    }
+}
+
+pub fn behaviour_transitions(
+    time: Res<Time>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut query: Query<(Entity, &mut Behaviour, &Perception, &Health), Without<Promoting>>,
+) {
+    let damaged: HashSet<Entity> = damage_events.iter().map(|damage_event| damage_event.target).collect();
+
+    for (entity, mut behaviour, perception, health) in &mut query {
+        behaviour.state_timer.tick(time.delta());
+
+        if behaviour.state == BehaviourState::Dead {
+            continue;
+        }
+        if health.current <= 0 {
+            let death_seconds = behaviour.config.death_seconds;
+            behaviour.set_state(BehaviourState::Dead, death_seconds);
+            continue;
+        }
+        // runs before damage is applied, so an invulnerable target here shrugs the hit off
+        if damaged.contains(&entity) && !health.is_invulnerable {
+            let stun_seconds = behaviour.config.stun_seconds;
+            behaviour.set_state(BehaviourState::Stunned, stun_seconds);
+            continue;
+        }
+
+        let in_attack_range = perception.sees_player && behaviour.config.attack.as_ref()
+            .is_some_and(|attack| perception.player_offset.length() <= attack.range);
+
+        match behaviour.state {
+            BehaviourState::Idle | BehaviourState::Patrol | BehaviourState::Chase if in_attack_range => {
+                let attack = behaviour.config.attack.clone().unwrap();
+                behaviour.set_state(BehaviourState::Attack, attack.windup_seconds + attack.lunge_seconds);
+            }
+            BehaviourState::Idle | BehaviourState::Patrol => {
+                if let (true, Some(chase)) = (perception.sees_player, behaviour.config.chase.clone()) {
+                    behaviour.set_state(BehaviourState::Chase, chase.give_up_seconds);
+                }
+            }
+            BehaviourState::Chase => {
+                if perception.sees_player {
+                    behaviour.state_timer.reset();
+                } else if behaviour.state_timer.finished() {
+                    let resting_state = behaviour.resting_state();
+                    behaviour.set_state(resting_state, 0.0);
+                }
+            }
+            BehaviourState::Attack | BehaviourState::Stunned => {
+                if behaviour.state_timer.finished() {
+                    match (perception.sees_player, behaviour.config.chase.clone()) {
+                        (true, Some(chase)) => behaviour.set_state(BehaviourState::Chase, chase.give_up_seconds),
+                        _ => {
+                            let resting_state = behaviour.resting_state();
+                            behaviour.set_state(resting_state, 0.0);
+                        }
+                    }
+                }
+            }
+            BehaviourState::Dead => (),
+        }
+    }
+}
+
+pub fn behaviour_actions(
+    time: Res<Time>,
+    mut screen_shake_events: EventWriter<ScreenShakeEvent>,
+    mut query: Query<(&mut Behaviour, &Perception, Option<&Navigator>, &Transform, &mut Velocity, &mut Sprite), Without<Promoting>>,
+) {
+    for (mut behaviour, perception, navigator, transform, mut velocity, mut sprite) in &mut query {
+        let face_player = if perception.player_offset.x < 0. { FacingDirection::Left } else { FacingDirection::Right };
+
+        let speed = match (behaviour.state, behaviour.config.clone()) {
+            (BehaviourState::Patrol, BehaviourConfig { patrol: Some(patrol), .. }) => {
+                // turn back once too far from home, pawns without a distance never turn
+                if let Some(distance) = patrol.distance {
+                    let from_home = transform.translation.x - behaviour.home.x;
+                    if (from_home <= -distance && behaviour.facing_direction == FacingDirection::Left)
+                        || (from_home >= distance && behaviour.facing_direction == FacingDirection::Right) {
+                        behaviour.facing_direction = behaviour.facing_direction.get_opposite();
+                    }
+                }
+                patrol.speed
+            }
+            (BehaviourState::Chase, BehaviourConfig { chase: Some(chase), .. }) => {
+                // follow the nav path when there is one, otherwise run straight at the player
+                behaviour.facing_direction = match navigator.and_then(|navigator| navigator.waypoint) {
+                    Some(waypoint) if waypoint.x < transform.translation.x => FacingDirection::Left,
+                    Some(_) => FacingDirection::Right,
+                    None => face_player,
+                };
+                chase.speed
+            }
+            (BehaviourState::Attack, BehaviourConfig { attack: Some(attack), .. }) => {
+                //wind up facing the player, then lunge
+                let elapsed = behaviour.state_timer.elapsed_secs();
+                if elapsed < attack.windup_seconds {
+                    behaviour.facing_direction = face_player;
+                    0.0
+                } else {
+                    if attack.slam_trauma > 0. && elapsed - time.delta_seconds() < attack.windup_seconds {
+                        screen_shake_events.send(ScreenShakeEvent { trauma: attack.slam_trauma });
+                    }
+                    attack.lunge_speed
+                }
+            }
+            _ => 0.0,
+        };
+
+        let direction = if behaviour.facing_direction == FacingDirection::Left { -1. } else { 1. };
+        velocity.linvel.x = direction * speed * time.delta_seconds();
+
+        let alpha = match behaviour.state {
+            BehaviourState::Stunned => 0.5,
+            BehaviourState::Dead => 1. - behaviour.state_timer.percent(),
+            _ => 1.0,
+        };
+        sprite.color.set_a(alpha);
+    }
+}
+
+pub fn contact_damage(
+    rapier_context: Res<RapierContext>,
+    player_query: Query<Entity, With<Player>>,
+    query: Query<(Entity, &Behaviour)>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    let Ok(player_entity) = player_query.get_single() else { return };
+
+    for (entity, behaviour) in &query {
+        if matches!(behaviour.state, BehaviourState::Stunned | BehaviourState::Dead) {
+            continue;
+        }
+
+        if let Some(contact_pair) = rapier_context.contact_pair(entity, player_entity) {
+            if contact_pair.has_any_active_contacts() {
+                damage_events.send(DamageEvent {
+                    target: player_entity,
+                    amount: behaviour.config.contact_damage,
+                });
+            }
+        }
+    }
+}
+
+pub fn despawn_dead_enemies(
+    mut commands: Commands,
+    query: Query<(Entity, &Behaviour)>,
+) {
+    for (entity, behaviour) in &query {
+        if behaviour.state == BehaviourState::Dead && behaviour.state_timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
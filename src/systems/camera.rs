@@ -1,28 +1,201 @@
 use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
 
 use crate::components::*;
+use crate::settings::DisplaySettings;
+
+/// Keeps the view inside the bounds, centering on any axis the bounds are too small to fill
+fn clamp_to_bounds(target: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let clamp_axis = |value: f32, half_view: f32, min: f32, max: f32| {
+        if max - min <= half_view * 2. {
+            (min + max) / 2.
+        } else {
+            value.clamp(min + half_view, max - half_view)
+        }
+    };
+
+    Vec2::new(
+        clamp_axis(target.x, half_view.x, bounds.min.x, bounds.max.x),
+        clamp_axis(target.y, half_view.y, bounds.min.y, bounds.max.y),
+    )
+}
+
+/// Frame rate independent exponential smoothing
+fn smoothing_factor(smoothing: f32, delta_seconds: f32) -> f32 {
+    1. - (-smoothing * delta_seconds).exp()
+}
 
 pub fn player_camera (
+    time: Res<Time>,
+    display_settings: Res<DisplaySettings>,
+    level_selection: Res<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    level_query: Query<(&Handle<LdtkLevel>, &Transform), (Without<PrimaryCamera>, Without<Player>)>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
     mut camera_query: Query<
         (
             &mut OrthographicProjection,
             &mut Transform,
+            &mut CameraController,
+            &mut CameraHandoff,
         ),
-        Without<Player>
+        (With<PrimaryCamera>, Without<Player>)
     >,
-    player_query: Query<&Transform, With<Player>>
+    camera_zone_query: Query<(Entity, &CameraZone, &GlobalTransform)>,
+    player_query: Query<(&Transform, &Velocity, &HorizontalMover, &GroundDetection), With<Player>>
 ) {
-    if let Ok(Transform {
-        translation: player_translation,
-        ..
-    }) = player_query.get_single() {
-        let player_translation = *player_translation;
+    if let Ok((
+        Transform {
+            translation: player_translation,
+            ..
+        },
+        velocity,
+        horizontal_mover,
+        ground_detection,
+    )) = player_query.get_single() {
+        let player_position = player_translation.truncate();
+        let delta_seconds = time.delta_seconds();
+
+        let (mut orthographic_projection, mut camera_transform, mut camera_controller, mut camera_handoff) = camera_query.single_mut();
+
+        // the window scale is left out of the controller's, so the view of the world is the same at any window size
+        let current_scale = match orthographic_projection.scaling_mode {
+            ScalingMode::WindowSize(scale) => scale / display_settings.scale,
+            _ => camera_controller.scale,
+        };
+
+        if !camera_controller.is_following {
+            camera_controller.is_following = true;
+            camera_controller.focus = player_position;
+            camera_controller.position = player_position + camera_controller.offset;
+        }
+
+        // drag the focus along once the player pushes against the edge of the deadzone
+        let deadzone = camera_controller.deadzone;
+        let mut focus = camera_controller.focus;
+        focus.x = focus.x.clamp(player_position.x - deadzone.x, player_position.x + deadzone.x);
+        if ground_detection.on_ground {
+            // platform snapping, only recenter vertically once the player has landed
+            focus.y += (player_position.y - focus.y) * smoothing_factor(camera_controller.smoothing, delta_seconds);
+        } else {
+            focus.y = focus.y.clamp(player_position.y - deadzone.y, player_position.y + deadzone.y);
+        }
+        camera_controller.focus = focus;
+
+        let look_ahead_target = match horizontal_mover.facing_direction {
+            FacingDirection::Left => -camera_controller.look_ahead_distance,
+            FacingDirection::Right => camera_controller.look_ahead_distance,
+        };
+        let look_ahead_factor = smoothing_factor(camera_controller.look_ahead_smoothing, delta_seconds);
+        camera_controller.look_ahead += (look_ahead_target - camera_controller.look_ahead) * look_ahead_factor;
+
+        let look_down_target = if velocity.linvel.y < -camera_controller.look_down_fall_speed {
+            -camera_controller.look_down_distance
+        } else {
+            0.
+        };
+        let look_down_factor = smoothing_factor(camera_controller.look_down_smoothing, delta_seconds);
+        camera_controller.look_down += (look_down_target - camera_controller.look_down) * look_down_factor;
+
+        let mut target = focus
+            + camera_controller.offset
+            + Vec2::new(camera_controller.look_ahead, camera_controller.look_down);
+
+        // the innermost zone the player is in wins
+        let camera_zone = camera_zone_query
+            .iter()
+            .filter(|(_, camera_zone, zone_transform)| {
+                let offset = (player_position - zone_transform.translation().truncate()).abs();
+                offset.x <= camera_zone.half_extents.x && offset.y <= camera_zone.half_extents.y
+            })
+            .min_by(|(_, a, _), (_, b, _)| {
+                let area = |zone: &CameraZone| zone.half_extents.x * zone.half_extents.y;
+                area(a).total_cmp(&area(b))
+            });
+
+        let mut target_scale = camera_controller.scale;
+        if let Some((_, camera_zone, zone_transform)) = camera_zone {
+            target = camera_zone.constrain(zone_transform.translation().truncate(), target);
+            target_scale = camera_zone.scale.unwrap_or(camera_controller.scale);
+        }
+        if let Some(cutscene_focus) = camera_controller.cutscene_focus {
+            target = cutscene_focus + camera_controller.offset;
+        }
+
+        let camera_zone_entity = camera_zone.map(|(entity, _, _)| entity);
+        if camera_zone_entity != camera_controller.camera_zone {
+            // blend with the zone being entered, or the one being left when entering none
+            let blend_seconds = camera_zone_entity
+                .or(camera_controller.camera_zone)
+                .and_then(|entity| camera_zone_query.get(entity).ok())
+                .map_or(camera_handoff.handoff_seconds, |(_, camera_zone, _)| camera_zone.blend_seconds);
+            let from = camera_transform.translation.truncate();
+            camera_handoff.start(from, current_scale, blend_seconds);
+            camera_controller.camera_zone = camera_zone_entity;
+        }
+
+        let mut scale = target_scale;
+        camera_handoff.timer.tick(time.delta());
+        let handoff_progress = if camera_handoff.timer.finished() {
+            None
+        } else {
+            let progress = camera_handoff.timer.percent();
+            Some(progress * progress * (3. - 2. * progress))
+        };
+        if let Some(eased) = handoff_progress {
+            scale = camera_handoff.from_scale + (target_scale - camera_handoff.from_scale) * eased;
+        }
+        orthographic_projection.scaling_mode = ScalingMode::WindowSize(scale * display_settings.scale);
+
+        // the projection's area is the part of the world in view, as of the last scale it was updated with
+        let half_view = orthographic_projection.area.size() / 2. * current_scale / scale;
+
+        let current_iid = ldtk_query
+            .get_single()
+            .ok()
+            .and_then(|ldtk_handle| ldtk_assets.get(ldtk_handle))
+            .and_then(|ldtk_asset| ldtk_asset.get_level(&level_selection))
+            .map(|level| level.iid.clone());
+
+        let current_bounds = level_query.iter().find_map(|(level_handle, level_transform)| {
+            let level = &ldtk_levels.get(level_handle)?.level;
+            if Some(&level.iid) != current_iid.as_ref() {
+                return None;
+            }
+            let min = level_transform.translation.truncate();
+            Some(Rect::from_corners(min, min + Vec2::new(level.px_wid as f32, level.px_hei as f32)))
+        });
+
+        if let Some(bounds) = current_bounds {
+            target = clamp_to_bounds(target, half_view, bounds);
+
+            let level_changed = camera_handoff.level_iid != current_iid;
+            if level_changed {
+                // no blending in from nowhere when the first level loads
+                if camera_handoff.level_iid.is_some() {
+                    let from = camera_transform.translation.truncate();
+                    let handoff_seconds = camera_handoff.handoff_seconds;
+                    camera_handoff.start(from, scale, handoff_seconds);
+                } else {
+                    camera_controller.position = target;
+                }
+                camera_handoff.level_iid = current_iid;
+            }
+        }
 
-        let (mut orthographic_projection, mut camera_transform) = camera_query.single_mut();
+        let position_factor = smoothing_factor(camera_controller.smoothing, delta_seconds);
+        let position = camera_controller.position.lerp(target, position_factor);
+        camera_controller.position = position;
 
-        orthographic_projection.scaling_mode = bevy::render::camera::ScalingMode::WindowSize(1.5);
+        let mut camera_position = position;
+        if let Some(eased) = handoff_progress {
+            camera_position = camera_handoff.from.lerp(position, eased);
+        }
 
-        camera_transform.translation.x = player_translation.x;
-        camera_transform.translation.y = player_translation.y + 50.;
+        camera_transform.translation.x = camera_position.x;
+        camera_transform.translation.y = camera_position.y;
     }
-}
\ No newline at end of file
+}
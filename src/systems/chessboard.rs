@@ -0,0 +1,303 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+
+use std::collections::HashSet;
+
+const ORTHOGONAL: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const DIAGONAL: [(i32, i32); 4] = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+const KNIGHT_JUMPS: [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+
+fn offset(square: GridCoords, (x, y): (i32, i32)) -> GridCoords {
+    GridCoords::new(square.x + x, square.y + y)
+}
+
+/// Walks in each direction until it leaves the board or runs into a piece,
+/// the square of the piece it runs into is still attacked
+fn slide(from: GridCoords, directions: &[(i32, i32)], board: &ChessBoard, occupied: &HashSet<GridCoords>) -> Vec<GridCoords> {
+    let mut squares = Vec::new();
+
+    for &direction in directions {
+        let mut square = offset(from, direction);
+        while board.contains(square) {
+            squares.push(square);
+            if occupied.contains(&square) {
+                break;
+            }
+            square = offset(square, direction);
+        }
+    }
+
+    squares
+}
+
+/// Squares a piece attacks.
+/// The pieces on the board are all black, so pawns attack downwards
+fn attacked_squares(piece: ChessPiece, from: GridCoords, board: &ChessBoard, occupied: &HashSet<GridCoords>) -> Vec<GridCoords> {
+    let jumps: Vec<GridCoords> = match piece {
+        ChessPiece::Pawn => [(-1, -1), (1, -1)].iter().map(|&o| offset(from, o)).collect(),
+        ChessPiece::Knight => KNIGHT_JUMPS.iter().map(|&o| offset(from, o)).collect(),
+        ChessPiece::King => ORTHOGONAL.iter().chain(DIAGONAL.iter()).map(|&o| offset(from, o)).collect(),
+        ChessPiece::Rook => return slide(from, &ORTHOGONAL, board, occupied),
+        ChessPiece::Bishop => return slide(from, &DIAGONAL, board, occupied),
+        ChessPiece::Queen => return slide(from, &[ORTHOGONAL, DIAGONAL].concat(), board, occupied),
+    };
+
+    jumps.into_iter().filter(|square| board.contains(*square)).collect()
+}
+
+/// Squares a piece can move to without taking one of its own side
+fn legal_moves(piece: ChessPiece, from: GridCoords, board: &ChessBoard, occupied: &HashSet<GridCoords>) -> Vec<GridCoords> {
+    let squares = if piece == ChessPiece::Pawn {
+        vec![offset(from, (0, -1))]
+    } else {
+        attacked_squares(piece, from, board, occupied)
+    };
+
+    squares
+        .into_iter()
+        .filter(|square| board.contains(*square) && !occupied.contains(square))
+        .collect()
+}
+
+fn distance_squared(a: GridCoords, b: GridCoords) -> i32 {
+    (a.x - b.x).pow(2) + (a.y - b.y).pow(2)
+}
+
+/// Places pieces on the board they were spawned on in LDtk
+pub fn attach_board_pieces(
+    mut piece_query: Query<(&mut BoardPiece, &mut GridCoords, &mut Transform, &Parent), Added<BoardPiece>>,
+    board_query: Query<(Entity, &ChessBoard, &Transform, &Parent), Without<BoardPiece>>,
+) {
+    for (mut board_piece, mut grid_coords, mut transform, piece_parent) in &mut piece_query {
+        for (board_entity, chess_board, board_transform, board_parent) in &board_query {
+            if board_parent.get() != piece_parent.get() {
+                continue;
+            }
+
+            let board_translation = board_transform.translation.truncate();
+            if let Some(square) = chess_board.square_at(board_translation, transform.translation.truncate()) {
+                board_piece.board = Some(board_entity);
+                *grid_coords = square;
+
+                let center = chess_board.square_center(board_translation, square);
+                transform.translation.x = center.x;
+                transform.translation.y = center.y;
+                break;
+            }
+        }
+    }
+}
+
+/// Every tick the board plays one move for black,
+/// then hurts the player if they are standing on a threatened square
+pub fn chessboard_tick(
+    time: Res<Time>,
+    mut board_query: Query<(Entity, &mut ChessBoard, &mut ThreatMap, &Transform, &GlobalTransform)>,
+    mut piece_query: Query<(Entity, &BoardPiece, &ChessPiece, &mut GridCoords, &mut Transform), Without<ChessBoard>>,
+    player_query: Query<(Entity, &GlobalTransform), With<Player>>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (board_entity, mut chess_board, mut threat_map, board_transform, board_global_transform) in &mut board_query {
+        chess_board.tick_timer.tick(time.delta());
+        if !chess_board.tick_timer.just_finished() {
+            continue;
+        }
+
+        let player_square = player_query.get_single().ok().and_then(|(player_entity, player_transform)| {
+            chess_board
+                .square_at(board_global_transform.translation().truncate(), player_transform.translation().truncate())
+                .map(|square| (player_entity, square))
+        });
+
+        let pieces: Vec<(Entity, ChessPiece, GridCoords)> = piece_query
+            .iter()
+            .filter(|(_, board_piece, ..)| board_piece.board == Some(board_entity))
+            .map(|(entity, _, &chess_piece, &grid_coords, _)| (entity, chess_piece, grid_coords))
+            .collect();
+        let mut occupied: HashSet<GridCoords> = pieces.iter().map(|(_, _, square)| *square).collect();
+
+        // black's move: take the player if possible, otherwise close in on them
+        if let Some((_, target)) = player_square {
+            let best_move = pieces
+                .iter()
+                .flat_map(|&(entity, chess_piece, from)| {
+                    let captures = attacked_squares(chess_piece, from, &chess_board, &occupied).contains(&target);
+                    let moves = if captures { vec![target] } else { legal_moves(chess_piece, from, &chess_board, &occupied) };
+
+                    moves.into_iter().map(move |to| (entity, from, to, captures))
+                })
+                .filter(|&(_, from, to, captures)| captures || distance_squared(to, target) < distance_squared(from, target))
+                .min_by_key(|&(_, _, to, captures)| (!captures, distance_squared(to, target)));
+
+            if let Some((entity, from, to, _)) = best_move {
+                if let Ok((.., mut grid_coords, mut transform)) = piece_query.get_mut(entity) {
+                    occupied.remove(&from);
+                    occupied.insert(to);
+                    *grid_coords = to;
+
+                    let center = chess_board.square_center(board_transform.translation.truncate(), to);
+                    transform.translation.x = center.x;
+                    transform.translation.y = center.y;
+                }
+            }
+        }
+
+        threat_map.threatened = piece_query
+            .iter()
+            .filter(|(_, board_piece, ..)| board_piece.board == Some(board_entity))
+            .flat_map(|(_, _, &chess_piece, &grid_coords, _)| attacked_squares(chess_piece, grid_coords, &chess_board, &occupied))
+            .collect();
+
+        if let Some((player_entity, square)) = player_square {
+            if threat_map.threatened.contains(&square) {
+                damage_events.send(DamageEvent {
+                    target: player_entity,
+                    amount: chess_board.damage,
+                });
+            }
+        }
+    }
+}
+
+/// Removes pieces the player has taken off the board,
+/// the board's threat map catches up on its next tick
+pub fn capture_board_pieces(
+    mut commands: Commands,
+    query: Query<(Entity, &Health), With<BoardPiece>>,
+) {
+    for (entity, health) in &query {
+        if health.current <= 0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Highlights every threatened square of a board whenever its threat map changes,
+/// reusing the overlay sprites it already has and only spawning more when it runs out
+pub fn render_threat_overlay(
+    mut commands: Commands,
+    board_query: Query<(Entity, &ChessBoard, &ThreatMap), Changed<ThreatMap>>,
+    mut overlay_query: Query<(&Parent, &mut Transform, &mut Visibility), With<ThreatOverlay>>,
+) {
+    for (board_entity, chess_board, threat_map) in &board_query {
+        // overlays are children of the board, so they are placed relative to its center
+        let mut centers = threat_map.threatened.iter().map(|&square| chess_board.square_center(Vec2::ZERO, square));
+
+        for (parent, mut transform, mut visibility) in &mut overlay_query {
+            if parent.get() != board_entity {
+                continue;
+            }
+
+            match centers.next() {
+                Some(center) => {
+                    transform.translation.x = center.x;
+                    transform.translation.y = center.y;
+                    *visibility = Visibility::Inherited;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+
+        commands.entity(board_entity).with_children(|board| {
+            for center in centers {
+                board
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::rgba(0.8, 0.1, 0.1, 0.35),
+                            custom_size: Some(Vec2::splat(chess_board.square_size)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(center.x, center.y, 0.1),
+                        ..default()
+                    })
+                    .insert(ThreatOverlay);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty 8x8 board
+    fn board() -> ChessBoard {
+        ChessBoard::default()
+    }
+
+    fn squares(coords: &[(i32, i32)]) -> HashSet<GridCoords> {
+        coords.iter().map(|&(x, y)| GridCoords::new(x, y)).collect()
+    }
+
+    fn sorted(squares: Vec<GridCoords>) -> Vec<(i32, i32)> {
+        let mut coords: Vec<(i32, i32)> = squares.into_iter().map(|square| (square.x, square.y)).collect();
+        coords.sort();
+        coords
+    }
+
+    #[test]
+    fn rook_slides_to_the_edges_of_an_empty_board() {
+        let from = GridCoords::new(0, 0);
+        let moves = legal_moves(ChessPiece::Rook, from, &board(), &squares(&[(0, 0)]));
+
+        assert_eq!(moves.len(), 14);
+        assert!(moves.contains(&GridCoords::new(7, 0)));
+        assert!(moves.contains(&GridCoords::new(0, 7)));
+    }
+
+    #[test]
+    fn slides_stop_at_the_first_piece_in_the_way() {
+        let from = GridCoords::new(0, 0);
+        let occupied = squares(&[(0, 0), (3, 0), (0, 2)]);
+
+        let attacked = sorted(attacked_squares(ChessPiece::Rook, from, &board(), &occupied));
+        assert_eq!(attacked, vec![(0, 1), (0, 2), (1, 0), (2, 0), (3, 0)]);
+
+        let moves = sorted(legal_moves(ChessPiece::Rook, from, &board(), &occupied));
+        assert_eq!(moves, vec![(0, 1), (1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn bishop_is_blocked_on_the_diagonal() {
+        let from = GridCoords::new(3, 3);
+        let occupied = squares(&[(3, 3), (5, 5)]);
+        let moves = legal_moves(ChessPiece::Bishop, from, &board(), &occupied);
+
+        assert!(moves.contains(&GridCoords::new(4, 4)));
+        assert!(!moves.contains(&GridCoords::new(5, 5)));
+        assert!(!moves.contains(&GridCoords::new(6, 6)));
+        assert!(moves.contains(&GridCoords::new(0, 0)));
+    }
+
+    #[test]
+    fn knight_jumps_over_pieces_but_stays_on_the_board() {
+        let from = GridCoords::new(0, 0);
+        let occupied = squares(&[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        let moves = sorted(legal_moves(ChessPiece::Knight, from, &board(), &occupied));
+
+        assert_eq!(moves, vec![(1, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn pawns_move_down_and_attack_diagonally() {
+        let from = GridCoords::new(4, 4);
+        let empty = squares(&[(4, 4)]);
+
+        assert_eq!(sorted(legal_moves(ChessPiece::Pawn, from, &board(), &empty)), vec![(4, 3)]);
+        assert_eq!(sorted(attacked_squares(ChessPiece::Pawn, from, &board(), &empty)), vec![(3, 3), (5, 3)]);
+
+        let blocked = squares(&[(4, 4), (4, 3)]);
+        assert!(legal_moves(ChessPiece::Pawn, from, &board(), &blocked).is_empty());
+    }
+
+    #[test]
+    fn king_in_the_corner_has_three_moves() {
+        let from = GridCoords::new(7, 7);
+        let moves = sorted(legal_moves(ChessPiece::King, from, &board(), &squares(&[(7, 7)])));
+
+        assert_eq!(moves, vec![(6, 6), (6, 7), (7, 6)]);
+    }
+}
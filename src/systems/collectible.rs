@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+
+/// Every collectible placed in a level, whether it has been picked up or not
+pub fn level_collectibles(level: &ldtk::Level) -> impl Iterator<Item = &EntityInstance> {
+    level
+        .layer_instances
+        .iter()
+        .flatten()
+        .flat_map(|layer_instance| &layer_instance.entity_instances)
+        .filter(|entity_instance| entity_instance.identifier == "Collectible")
+}
+
+/// How many of a level's collectibles have been picked up, out of how many there are
+pub fn level_completion(level: &ldtk::Level, progress: &Progress) -> (usize, usize) {
+    level_collectibles(level).fold((0, 0), |(collected, total), entity_instance| {
+        let is_collected = progress.collected_items.contains(&entity_instance.iid);
+        (collected + is_collected as usize, total + 1)
+    })
+}
+
+/// Picks up whatever the player's collider is touching, as soon as any part of it overlaps the collectible's sensor
+pub fn collect_collectibles(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    mut progress: ResMut<Progress>,
+    mut player_events: EventWriter<PlayerEvent>,
+    collectible_query: Query<(Entity, &EntityIid), With<Collectible>>,
+    player_query: Query<Entity, With<Player>>,
+) {
+    let Ok(player_entity) = player_query.get_single() else { return };
+
+    for (collectible_entity, entity_iid) in &collectible_query {
+        if rapier_context.intersection_pair(player_entity, collectible_entity) != Some(true) {
+            continue;
+        }
+
+        // the same collectible can still be here the frame after it was picked up, before its despawn has gone through
+        if progress.collected_items.insert(entity_iid.as_str().to_string()) {
+            commands.entity(collectible_entity).despawn_recursive();
+            player_events.send(PlayerEvent::Collected);
+        }
+    }
+}
+
+/// Collectibles already in the save don't come back when their level is loaded again
+pub fn despawn_collected(
+    mut commands: Commands,
+    progress: Res<Progress>,
+    query: Query<(Entity, &EntityIid), Added<Collectible>>,
+) {
+    for (entity, entity_iid) in &query {
+        if progress.collected_items.contains(entity_iid.as_str()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::settings::*;
+
+use std::collections::HashSet;
+
+pub fn player_attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    player_input: PlayerInput,
+    mut query: Query<(Entity, &mut Attacker, &HorizontalMover, &GroundDetection, &Collider), With<Player>>,
+    hitbox_query: Query<(Entity, &AttackHitbox)>,
+) {
+    for (entity, mut attacker, horizontal_mover, ground_detection, collider) in &mut query {
+        //start the attack
+        if player_input.just_pressed(InputAction::Attack) && attacker.can_attack && !horizontal_mover.is_dashing {
+            attacker.can_attack = false;
+            attacker.is_attacking = true;
+            attacker.attack_timer.reset();
+
+            attacker.attack_direction = if player_input.pressed(InputAction::Up) {
+                AttackDirection::Up
+            } else if player_input.pressed(InputAction::Down) && !ground_detection.on_ground { //pogo
+                AttackDirection::Down
+            } else {
+                AttackDirection::Forward
+            };
+
+            let half_extents = collider.as_cuboid().map(|cuboid| cuboid.half_extents()).unwrap_or(Vec2::new(12., 16.));
+            let half_reach = attacker.reach / 2.;
+            let facing = if horizontal_mover.facing_direction == FacingDirection::Left { -1. } else { 1. };
+
+            let (hitbox_shape, hitbox_translation) = match attacker.attack_direction {
+                AttackDirection::Forward => (
+                    Collider::cuboid(half_reach, half_extents.y * 0.75),
+                    Vec3::new(facing * (half_extents.x + half_reach), 0., 0.),
+                ),
+                AttackDirection::Up => (
+                    Collider::cuboid(half_extents.x * 1.5, half_reach),
+                    Vec3::new(0., half_extents.y + half_reach, 0.),
+                ),
+                AttackDirection::Down => (
+                    Collider::cuboid(half_extents.x * 1.5, half_reach),
+                    Vec3::new(0., -(half_extents.y + half_reach), 0.),
+                ),
+            };
+
+            let attack_direction = attacker.attack_direction;
+            let damage = attacker.damage;
+            commands.entity(entity).with_children(|builder| {
+                builder
+                    .spawn_empty()
+                    .insert(hitbox_shape)
+                    .insert(Sensor)
+                    .insert(Transform::from_translation(hitbox_translation))
+                    .insert(GlobalTransform::default())
+                    .insert(AttackHitbox {
+                        attacker_entity: entity,
+                        direction: attack_direction,
+                        damage,
+                        hit_entities: HashSet::new(),
+                    });
+            });
+        }
+        //while attacking
+        if attacker.is_attacking {
+            if !attacker.attack_timer.finished() {
+                attacker.attack_timer.tick(time.delta());
+            } else {
+                attacker.is_attacking = false;
+                attacker.is_recovering = true;
+                attacker.recovery_timer.reset();
+                attacker.attack_cooldown_timer.reset();
+
+                for (hitbox_entity, hitbox) in &hitbox_query {
+                    if hitbox.attacker_entity == entity {
+                        commands.entity(hitbox_entity).despawn_recursive();
+                    }
+                }
+            }
+        }
+        //recovery after the attack, the player can't dash until it's over
+        if attacker.is_recovering {
+            if !attacker.recovery_timer.finished() {
+                attacker.recovery_timer.tick(time.delta());
+            } else {
+                attacker.is_recovering = false;
+            }
+        }
+        //cooldown before the next attack
+        if !attacker.can_attack && !attacker.is_attacking {
+            if !attacker.attack_cooldown_timer.finished() {
+                attacker.attack_cooldown_timer.tick(time.delta());
+            } else {
+                attacker.can_attack = true;
+            }
+        }
+    }
+}
+
+/// Damages every enemy an attack hitbox touches, once per attack.
+/// Landing a down attack bounces the attacker back up like a pogo stick
+pub fn attack_hits(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    rapier_config: Res<RapierConfiguration>,
+    mut hitbox_query: Query<(Entity, &mut AttackHitbox)>,
+    enemy_query: Query<(), (With<Enemy>, With<Health>)>,
+    mut attacker_query: Query<(&Attacker, &mut Velocity, &mut VerticalMover, &mut HorizontalMover, &GravityScale)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut hit_stop_events: EventWriter<HitStopEvent>,
+    mut screen_shake_events: EventWriter<ScreenShakeEvent>,
+) {
+    for (hitbox_entity, mut hitbox) in &mut hitbox_query {
+        let mut landed_hit = false;
+
+        for (collider1, collider2, intersecting) in rapier_context.intersections_with(hitbox_entity) {
+            let other = if collider1 == hitbox_entity { collider2 } else { collider1 };
+
+            if intersecting && enemy_query.contains(other) && hitbox.hit_entities.insert(other) {
+                damage_events.send(DamageEvent {
+                    target: other,
+                    amount: hitbox.damage,
+                });
+                landed_hit = true;
+            }
+        }
+
+        if !landed_hit {
+            continue;
+        }
+
+        if let Ok((attacker, mut velocity, mut vertical_mover, mut horizontal_mover, gravity_scale)) = attacker_query.get_mut(hitbox.attacker_entity) {
+            hit_stop_events.send(HitStopEvent { seconds: attacker.hit_stop_seconds });
+            screen_shake_events.send(ScreenShakeEvent { trauma: 0.2 });
+
+            if hitbox.direction == AttackDirection::Down {
+                let pogo_power = (-2. * rapier_config.gravity.y * gravity_scale.0 * attacker.pogo_height).sqrt();
+                velocity.linvel.y = pogo_power * time.delta_seconds();
+
+                vertical_mover.jump_count = vertical_mover.max_jump_count;
+                horizontal_mover.can_dash = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::components::*;
+use crate::cutscene::*;
+use crate::dialogue::*;
+use crate::resources::*;
+use crate::settings::*;
+use crate::systems::dialogue::{begin_dialogue, end_dialogue};
+
+/// How close the player has to get to where they're walked to
+const WALK_TOLERANCE: f32 = 4.;
+/// A walk gives up after this long, so a wall in the way can't hold the cutscene forever
+const WALK_TIMEOUT_SECONDS: f32 = 5.;
+/// How long the skip key has to be held down to skip a cutscene
+const SKIP_HOLD_SECONDS: f32 = 0.75;
+
+fn spawn_cutscene_sprite(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    cutscene_state: &CutsceneState,
+    level_query: &Query<&GlobalTransform>,
+    image: &str,
+    at: (f32, f32),
+    size: (f32, f32),
+) {
+    let Some(level) = cutscene_state.level else { return };
+    let Ok(level_transform) = level_query.get(level) else { return };
+    let translation = cutscene_state.origin + Vec2::from(at) - level_transform.translation().truncate();
+
+    commands.entity(level).with_children(|level| {
+        level.spawn((
+            SpriteBundle {
+                texture: asset_server.load(image),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::from(size)),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation.extend(0.)),
+                ..default()
+            },
+            CutsceneSprite,
+        ));
+    });
+}
+
+/// The camera's current scale, without the window scale, for blending away from
+fn camera_scale(orthographic_projection: &OrthographicProjection, camera_controller: &CameraController, display_settings: &DisplaySettings) -> f32 {
+    match orthographic_projection.scaling_mode {
+        ScalingMode::WindowSize(scale) => scale / display_settings.scale,
+        _ => camera_controller.scale,
+    }
+}
+
+/// Starts loading each trigger's script as soon as it spawns, triggers that have already played for good are removed
+pub fn load_trigger_cutscenes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    progress: Res<Progress>,
+    mut query: Query<(Entity, &EntityIid, &mut Trigger), Added<Trigger>>,
+) {
+    for (entity, entity_iid, mut trigger) in &mut query {
+        if trigger.cutscene_id.is_empty() {
+            warn!("Trigger has no Cutscene");
+            continue;
+        }
+        if trigger.once && progress.played_cutscenes.contains(entity_iid.as_str()) {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        trigger.script = asset_server.load(format!("cutscenes/{}.cutscene.ron", trigger.cutscene_id));
+    }
+}
+
+/// Walking into a trigger plays its cutscene, unless something else already has control of the player
+pub fn start_cutscenes(
+    mut commands: Commands,
+    cutscene_scripts: Res<Assets<CutsceneScript>>,
+    mut cutscene_state: ResMut<CutsceneState>,
+    mut progress: ResMut<Progress>,
+    mut input_lock: ResMut<InputLock>,
+    key_bindings: Res<KeyBindings>,
+    mut trigger_query: Query<(Entity, &mut Trigger, &EntityIid, &GlobalTransform)>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    parent_query: Query<&Parent>,
+    level_query: Query<(), With<Handle<LdtkLevel>>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return };
+    let player_translation = player_transform.translation().truncate();
+
+    for (entity, mut trigger, entity_iid, trigger_transform) in &mut trigger_query {
+        let trigger_translation = trigger_transform.translation().truncate();
+        let offset = (player_translation - trigger_translation).abs();
+        let is_player_inside = offset.x <= trigger.half_extents.x && offset.y <= trigger.half_extents.y;
+        let has_entered = is_player_inside && !trigger.is_player_inside;
+        trigger.is_player_inside = is_player_inside;
+
+        if !has_entered || cutscene_state.is_active() || input_lock.is_locked() {
+            continue;
+        }
+        if trigger.once && progress.played_cutscenes.contains(entity_iid.as_str()) {
+            continue;
+        }
+        if cutscene_scripts.get(&trigger.script).is_none() {
+            warn!("Cutscene {} isn't loaded", trigger.cutscene_id);
+            continue;
+        }
+
+        if trigger.once {
+            progress.played_cutscenes.insert(entity_iid.as_str().to_string());
+        }
+        *cutscene_state = CutsceneState {
+            script: Some(trigger.script.clone()),
+            origin: trigger_translation,
+            level: parent_query.iter_ancestors(entity).find(|ancestor| level_query.contains(*ancestor)),
+            ..default()
+        };
+        input_lock.lock(InputLockReason::Cutscene);
+
+        let key = key_bindings.keys(InputAction::Skip).first().map_or("Skip".to_string(), |key| format!("{:?}", key));
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(16.),
+                        top: Val::Px(16.),
+                        ..default()
+                    },
+                    z_index: ZIndex::Global(35),
+                    ..default()
+                },
+                CutsceneHint,
+            ))
+            .with_children(|hint| {
+                hint.spawn(TextBundle::from_section(format!("Hold {} to skip", key), TextStyle {
+                    font_size: 14.,
+                    color: Color::rgba(1., 1., 1., 0.7),
+                    ..default()
+                }));
+            });
+    }
+}
+
+/// Plays the steps of the cutscene one after another, steps that finish straight away don't take up a frame
+pub fn run_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    display_settings: Res<DisplaySettings>,
+    cutscene_scripts: Res<Assets<CutsceneScript>>,
+    dialogue_scripts: Res<Assets<DialogueScript>>,
+    mut cutscene_state: ResMut<CutsceneState>,
+    mut dialogue_state: ResMut<DialogueState>,
+    mut progress: ResMut<Progress>,
+    mut input_lock: ResMut<InputLock>,
+    mut scripted_input: ResMut<ScriptedInput>,
+    mut camera_query: Query<(&OrthographicProjection, &Transform, &mut CameraController, &mut CameraHandoff), With<PrimaryCamera>>,
+    mut player_query: Query<(&GlobalTransform, &mut Animator), With<Player>>,
+    level_query: Query<&GlobalTransform>,
+) {
+    let Some(script_handle) = cutscene_state.script.clone() else { return };
+    let Some(script) = cutscene_scripts.get(&script_handle) else { return };
+
+    cutscene_state.step_timer.tick(time.delta());
+
+    while let Some(step) = script.steps.get(cutscene_state.step) {
+        let is_starting = !cutscene_state.is_step_started;
+        cutscene_state.is_step_started = true;
+
+        let is_finished = match step {
+            CutsceneStep::MoveCamera { to, seconds } => {
+                if is_starting {
+                    cutscene_state.step_timer = Timer::from_seconds(*seconds, TimerMode::Once);
+                    for (orthographic_projection, camera_transform, mut camera_controller, mut camera_handoff) in &mut camera_query {
+                        let from_scale = camera_scale(orthographic_projection, &camera_controller, &display_settings);
+                        camera_handoff.start(camera_transform.translation.truncate(), from_scale, *seconds);
+                        camera_controller.cutscene_focus = Some(cutscene_state.origin + Vec2::from(*to));
+                    }
+                }
+                cutscene_state.step_timer.finished()
+            }
+            CutsceneStep::ReleaseCamera => {
+                for (orthographic_projection, camera_transform, mut camera_controller, mut camera_handoff) in &mut camera_query {
+                    let from_scale = camera_scale(orthographic_projection, &camera_controller, &display_settings);
+                    let handoff_seconds = camera_handoff.handoff_seconds;
+                    camera_handoff.start(camera_transform.translation.truncate(), from_scale, handoff_seconds);
+                    camera_controller.cutscene_focus = None;
+                }
+                true
+            }
+            CutsceneStep::WalkPlayer { to_x } => {
+                if is_starting {
+                    cutscene_state.step_timer = Timer::from_seconds(WALK_TIMEOUT_SECONDS, TimerMode::Once);
+                }
+                scripted_input.pressed.clear();
+                let distance = player_query
+                    .get_single()
+                    .map_or(0., |(player_transform, _)| cutscene_state.origin.x + to_x - player_transform.translation().x);
+
+                if distance.abs() <= WALK_TOLERANCE || cutscene_state.step_timer.finished() {
+                    true
+                } else {
+                    let action = if distance < 0. { InputAction::Left } else { InputAction::Right };
+                    scripted_input.pressed.insert(action);
+                    false
+                }
+            }
+            CutsceneStep::PlayAnimation { clip, seconds } => {
+                if is_starting {
+                    cutscene_state.step_timer = Timer::from_seconds(*seconds, TimerMode::Once);
+                    for (_, mut animator) in &mut player_query {
+                        animator.scripted_clip = Some(clip.clone());
+                    }
+                }
+                let is_finished = cutscene_state.step_timer.finished();
+                if is_finished {
+                    for (_, mut animator) in &mut player_query {
+                        animator.scripted_clip = None;
+                    }
+                }
+                is_finished
+            }
+            CutsceneStep::ShowDialogue { id } => {
+                if is_starting {
+                    cutscene_state.dialogue = Some(asset_server.load(format!("dialogue/{}.dialogue.ron", id)));
+                    cutscene_state.is_dialogue_started = false;
+                }
+                if cutscene_state.is_dialogue_started {
+                    !dialogue_state.is_active()
+                } else {
+                    let dialogue_handle = cutscene_state.dialogue.clone().unwrap_or_default();
+                    match dialogue_scripts.get(&dialogue_handle) {
+                        Some(dialogue_script) => {
+                            begin_dialogue(&mut commands, &mut dialogue_state, &mut progress, &mut input_lock, dialogue_handle.clone(), dialogue_script);
+                            cutscene_state.is_dialogue_started = true;
+                            false
+                        }
+                        // a dialogue that can't be found is passed over rather than leaving the player stuck
+                        None => {
+                            let is_failed = asset_server.get_load_state(&dialogue_handle) == LoadState::Failed;
+                            if is_failed {
+                                warn!("Dialogue {} couldn't be loaded", id);
+                            }
+                            is_failed
+                        }
+                    }
+                }
+            }
+            CutsceneStep::Wait { seconds } => {
+                if is_starting {
+                    cutscene_state.step_timer = Timer::from_seconds(*seconds, TimerMode::Once);
+                }
+                cutscene_state.step_timer.finished()
+            }
+            CutsceneStep::Spawn { image, at, size } => {
+                spawn_cutscene_sprite(&mut commands, &asset_server, &cutscene_state, &level_query, image, *at, *size);
+                true
+            }
+            CutsceneStep::Fade { to, seconds } => {
+                if is_starting {
+                    cutscene_state.step_timer = Timer::from_seconds(*seconds, TimerMode::Once);
+                    cutscene_state.fade_from = cutscene_state.fade;
+                }
+                let percent = cutscene_state.step_timer.percent();
+                cutscene_state.fade = cutscene_state.fade_from + (to - cutscene_state.fade_from) * percent;
+                cutscene_state.step_timer.finished()
+            }
+        };
+
+        if !is_finished {
+            break;
+        }
+        cutscene_state.step += 1;
+        cutscene_state.is_step_started = false;
+    }
+}
+
+/// Holding the skip key skips to the end, though anything the rest of the cutscene spawns or wherever it walks the player to still happens
+pub fn skip_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    asset_server: Res<AssetServer>,
+    cutscene_scripts: Res<Assets<CutsceneScript>>,
+    mut cutscene_state: ResMut<CutsceneState>,
+    mut dialogue_state: ResMut<DialogueState>,
+    mut input_lock: ResMut<InputLock>,
+    mut player_query: Query<(&GlobalTransform, &mut Transform), With<Player>>,
+    level_query: Query<&GlobalTransform>,
+    box_query: Query<Entity, With<DialogueBox>>,
+) {
+    let Some(script_handle) = cutscene_state.script.clone() else { return };
+
+    if key_bindings.pressed(&input, InputAction::Skip) {
+        cutscene_state.skip_seconds += time.delta_seconds();
+    } else {
+        cutscene_state.skip_seconds = 0.;
+    }
+    if cutscene_state.skip_seconds < SKIP_HOLD_SECONDS {
+        return;
+    }
+
+    let Some(script) = cutscene_scripts.get(&script_handle) else { return };
+    for step in script.steps.iter().skip(cutscene_state.step) {
+        match step {
+            CutsceneStep::WalkPlayer { to_x } => {
+                for (player_global_transform, mut player_transform) in &mut player_query {
+                    player_transform.translation.x += cutscene_state.origin.x + to_x - player_global_transform.translation().x;
+                }
+            }
+            CutsceneStep::Spawn { image, at, size } => {
+                spawn_cutscene_sprite(&mut commands, &asset_server, &cutscene_state, &level_query, image, *at, *size);
+            }
+            _ => (),
+        }
+    }
+
+    if dialogue_state.is_active() {
+        end_dialogue(&mut commands, &mut dialogue_state, &mut input_lock, &box_query);
+    }
+    cutscene_state.step = script.steps.len();
+}
+
+/// Gives control back once the last step is done, putting back anything the cutscene took over
+pub fn finish_cutscene(
+    mut commands: Commands,
+    display_settings: Res<DisplaySettings>,
+    cutscene_scripts: Res<Assets<CutsceneScript>>,
+    mut cutscene_state: ResMut<CutsceneState>,
+    mut input_lock: ResMut<InputLock>,
+    mut scripted_input: ResMut<ScriptedInput>,
+    mut camera_query: Query<(&OrthographicProjection, &Transform, &mut CameraController, &mut CameraHandoff), With<PrimaryCamera>>,
+    mut animator_query: Query<&mut Animator, With<Player>>,
+    hint_query: Query<Entity, With<CutsceneHint>>,
+) {
+    let Some(script_handle) = &cutscene_state.script else { return };
+    let step_count = cutscene_scripts.get(script_handle).map_or(0, |script| script.steps.len());
+    if cutscene_state.step < step_count {
+        return;
+    }
+
+    for (orthographic_projection, camera_transform, mut camera_controller, mut camera_handoff) in &mut camera_query {
+        if camera_controller.cutscene_focus.is_some() {
+            let from_scale = camera_scale(orthographic_projection, &camera_controller, &display_settings);
+            let handoff_seconds = camera_handoff.handoff_seconds;
+            camera_handoff.start(camera_transform.translation.truncate(), from_scale, handoff_seconds);
+            camera_controller.cutscene_focus = None;
+        }
+    }
+    for mut animator in &mut animator_query {
+        animator.scripted_clip = None;
+    }
+    scripted_input.pressed.clear();
+    input_lock.unlock(InputLockReason::Cutscene);
+    *cutscene_state = CutsceneState::default();
+
+    for entity in &hint_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
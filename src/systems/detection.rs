@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::components::*;
+use crate::events::*;
 
 pub fn ground_detection(
     mut ground_sensors: Query<&mut GroundSensor>,
@@ -37,12 +38,22 @@ pub fn ground_detection(
 }
 
 pub fn update_on_ground(
-    mut ground_detectors: Query<&mut GroundDetection>,
+    mut ground_detectors: Query<(&mut GroundDetection, &Velocity, Option<&Player>)>,
     ground_sensors: Query<&GroundSensor, Changed<GroundSensor>>,
+    mut player_events: EventWriter<PlayerEvent>,
 ) {
     for sensor in &ground_sensors {
-        if let Ok(mut ground_detection) = ground_detectors.get_mut(sensor.ground_detection_entity) {
-            ground_detection.on_ground = !sensor.intersecting_ground_entities.is_empty();
+        if let Ok((mut ground_detection, velocity, player)) = ground_detectors.get_mut(sensor.ground_detection_entity) {
+            let on_ground = !sensor.intersecting_ground_entities.is_empty();
+
+            // the sensor reaches a little below the collider, so the fall hasn't been stopped yet
+            if player.is_some() && on_ground && !ground_detection.on_ground {
+                player_events.send(PlayerEvent::Landed {
+                    impact_speed: (-velocity.linvel.y).max(0.),
+                });
+            }
+
+            ground_detection.on_ground = on_ground;
         }
     }
 }
@@ -0,0 +1,340 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::dialogue::*;
+use crate::resources::*;
+use crate::settings::*;
+
+/// How far outside an NPC the player can still talk to them
+const TALK_DISTANCE: f32 = 16.;
+
+fn is_near(npc: &Npc, npc_translation: Vec2, player_translation: Vec2) -> bool {
+    let offset = (player_translation - npc_translation).abs();
+    offset.x <= npc.half_extents.x + TALK_DISTANCE && offset.y <= npc.half_extents.y + TALK_DISTANCE
+}
+
+/// Moves on to a line, setting its flags as soon as it's shown
+fn enter_line(dialogue_state: &mut DialogueState, progress: &mut Progress, script: &DialogueScript, line_name: &str) {
+    dialogue_state.line = line_name.to_string();
+    dialogue_state.shown_characters = 0.;
+    dialogue_state.selected_choice = 0;
+
+    match script.lines.get(line_name) {
+        Some(line) => progress.dialogue_flags.extend(line.set_flags.iter().cloned()),
+        None => warn!("Dialogue line {} doesn't exist", line_name),
+    }
+}
+
+fn spawn_dialogue_box(commands: &mut Commands) {
+    let text_style = TextStyle {
+        font_size: 16.,
+        color: Color::WHITE,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(16.),
+                    right: Val::Px(16.),
+                    bottom: Val::Px(16.),
+                    min_height: Val::Px(96.),
+                    padding: UiRect::all(Val::Px(8.)),
+                    column_gap: Val::Px(8.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.85).into(),
+                z_index: ZIndex::Global(30),
+                ..default()
+            },
+            DialogueBox,
+        ))
+        .with_children(|dialogue_box| {
+            dialogue_box.spawn((
+                ImageBundle {
+                    style: Style {
+                        width: Val::Px(64.),
+                        height: Val::Px(64.),
+                        display: Display::None,
+                        ..default()
+                    },
+                    ..default()
+                },
+                DialoguePortrait,
+            ));
+            dialogue_box
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        flex_grow: 1.,
+                        row_gap: Val::Px(4.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|column| {
+                    column.spawn((
+                        TextBundle::from_section("", TextStyle {
+                            color: Color::rgb(1., 0.85, 0.5),
+                            ..text_style.clone()
+                        }),
+                        DialogueSpeakerText,
+                    ));
+                    column.spawn((TextBundle::from_section("", text_style.clone()), DialogueText));
+                    column.spawn((
+                        TextBundle::from_section("", TextStyle {
+                            color: Color::rgb(0.8, 0.8, 0.8),
+                            ..text_style
+                        }),
+                        DialogueChoicesText,
+                    ));
+                });
+        });
+}
+
+/// Opens the dialogue box on the script's start line and takes control away from the player until it closes
+pub fn begin_dialogue(
+    commands: &mut Commands,
+    dialogue_state: &mut DialogueState,
+    progress: &mut Progress,
+    input_lock: &mut InputLock,
+    script_handle: Handle<DialogueScript>,
+    script: &DialogueScript,
+) {
+    let start_line = script.start_line(&progress.dialogue_flags).to_string();
+    dialogue_state.script = Some(script_handle);
+    enter_line(dialogue_state, progress, script, &start_line);
+    input_lock.lock(InputLockReason::Dialogue);
+    spawn_dialogue_box(commands);
+}
+
+pub fn end_dialogue(
+    commands: &mut Commands,
+    dialogue_state: &mut DialogueState,
+    input_lock: &mut InputLock,
+    box_query: &Query<Entity, With<DialogueBox>>,
+) {
+    dialogue_state.script = None;
+    input_lock.unlock(InputLockReason::Dialogue);
+    for entity in box_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Starts loading each NPC's script as soon as they spawn, and gives them a prompt to show when the player is near
+pub fn load_npc_dialogue(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    key_bindings: Res<KeyBindings>,
+    mut query: Query<(Entity, &mut Npc), Added<Npc>>,
+) {
+    for (entity, mut npc) in &mut query {
+        if npc.dialogue_id.is_empty() {
+            warn!("NPC has no Dialogue_id");
+            continue;
+        }
+        npc.script = asset_server.load(format!("dialogue/{}.dialogue.ron", npc.dialogue_id));
+
+        let key = key_bindings.keys(InputAction::Up).first().map_or("Up".to_string(), |key| format!("{:?}", key));
+        let prompt_height = npc.half_extents.y + 12.;
+        commands.entity(entity).with_children(|npc| {
+            npc.spawn((
+                Text2dBundle {
+                    text: Text::from_section(format!("{}: Talk", key), TextStyle {
+                        font_size: 12.,
+                        color: Color::WHITE,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0., prompt_height, 1.),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                NpcPrompt,
+            ));
+        });
+    }
+}
+
+pub fn npc_prompts(
+    dialogue_state: Res<DialogueState>,
+    npc_query: Query<(&Npc, &GlobalTransform, &Children)>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut prompt_query: Query<&mut Visibility, With<NpcPrompt>>,
+) {
+    let player_translation = player_query.get_single().ok().map(|transform| transform.translation().truncate());
+
+    for (npc, npc_transform, children) in &npc_query {
+        let is_shown = !dialogue_state.is_active()
+            && player_translation.is_some_and(|player_translation| is_near(npc, npc_transform.translation().truncate(), player_translation));
+
+        for child in children {
+            if let Ok(mut visibility) = prompt_query.get_mut(*child) {
+                let new_visibility = if is_shown { Visibility::Inherited } else { Visibility::Hidden };
+                if *visibility != new_visibility {
+                    *visibility = new_visibility;
+                }
+            }
+        }
+    }
+}
+
+/// Pressing up next to an NPC starts talking to them
+pub fn start_dialogue(
+    mut commands: Commands,
+    player_input: PlayerInput,
+    dialogue_scripts: Res<Assets<DialogueScript>>,
+    mut dialogue_state: ResMut<DialogueState>,
+    mut progress: ResMut<Progress>,
+    mut input_lock: ResMut<InputLock>,
+    npc_query: Query<(&Npc, &GlobalTransform)>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+) {
+    if dialogue_state.is_active() || !player_input.just_pressed(InputAction::Up) {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else { return };
+    let player_translation = player_transform.translation().truncate();
+
+    let Some((npc, _)) = npc_query
+        .iter()
+        .find(|(npc, npc_transform)| is_near(npc, npc_transform.translation().truncate(), player_translation))
+    else {
+        return;
+    };
+    let Some(script) = dialogue_scripts.get(&npc.script) else {
+        warn!("Dialogue {} isn't loaded", npc.dialogue_id);
+        return;
+    };
+
+    begin_dialogue(&mut commands, &mut dialogue_state, &mut progress, &mut input_lock, npc.script.clone(), script);
+}
+
+/// Types out the current line, then jump or enter moves on, with up and down picking between any choices.
+/// Gameplay input is locked, so this reads the keys directly
+pub fn advance_dialogue(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut input: ResMut<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    dialogue_scripts: Res<Assets<DialogueScript>>,
+    mut dialogue_state: ResMut<DialogueState>,
+    mut progress: ResMut<Progress>,
+    mut input_lock: ResMut<InputLock>,
+    box_query: Query<Entity, With<DialogueBox>>,
+) {
+    let Some(script_handle) = dialogue_state.script.clone() else { return };
+    let line = dialogue_scripts
+        .get(&script_handle)
+        .and_then(|script| Some((script, script.lines.get(&dialogue_state.line)?)));
+
+    let next_line = match line {
+        Some((script, line)) => {
+            let line_length = line.text.chars().count() as f32;
+            let is_typing = dialogue_state.shown_characters < line_length;
+            if is_typing {
+                dialogue_state.shown_characters = (dialogue_state.shown_characters + dialogue_state.characters_per_second * time.delta_seconds()).min(line_length);
+            }
+
+            let choices: Vec<&DialogueChoice> = line.choices.iter().filter(|choice| choice.is_available(&progress.dialogue_flags)).collect();
+            if !is_typing && !choices.is_empty() {
+                if key_bindings.just_pressed(&input, InputAction::Down) {
+                    dialogue_state.selected_choice = (dialogue_state.selected_choice + 1) % choices.len();
+                }
+                if key_bindings.just_pressed(&input, InputAction::Up) {
+                    dialogue_state.selected_choice = (dialogue_state.selected_choice + choices.len() - 1) % choices.len();
+                }
+            }
+
+            if !key_bindings.just_pressed(&input, InputAction::Jump) && !input.just_pressed(KeyCode::Return) {
+                return;
+            }
+            // the player gets control back as soon as the conversation ends, and shouldn't jump with the key that ended it
+            for key in key_bindings.keys(InputAction::Jump).iter().chain([&KeyCode::Return]) {
+                input.clear_just_pressed(*key);
+            }
+            // skip to the end of the line first, so a line can't be missed by pressing too early
+            if is_typing {
+                dialogue_state.shown_characters = line_length;
+                return;
+            }
+
+            let next_line = match choices.get(dialogue_state.selected_choice) {
+                Some(choice) => {
+                    progress.dialogue_flags.extend(choice.set_flags.iter().cloned());
+                    choice.next.clone()
+                }
+                None => line.next.clone(),
+            };
+            next_line.map(|next_line| (script, next_line))
+        }
+        // a missing script or line ends the conversation rather than leaving the player stuck
+        None => None,
+    };
+
+    match next_line {
+        Some((script, next_line)) => enter_line(&mut dialogue_state, &mut progress, script, &next_line),
+        None => end_dialogue(&mut commands, &mut dialogue_state, &mut input_lock, &box_query),
+    }
+}
+
+pub fn update_dialogue_box(
+    asset_server: Res<AssetServer>,
+    dialogue_scripts: Res<Assets<DialogueScript>>,
+    dialogue_state: Res<DialogueState>,
+    progress: Res<Progress>,
+    mut portrait_query: Query<(&mut UiImage, &mut Style), With<DialoguePortrait>>,
+    mut text_query: Query<(&mut Text, Option<&DialogueSpeakerText>, Option<&DialogueText>, Option<&DialogueChoicesText>)>,
+) {
+    let Some(line) = dialogue_state
+        .script
+        .as_ref()
+        .and_then(|script_handle| dialogue_scripts.get(script_handle))
+        .and_then(|script| script.lines.get(&dialogue_state.line))
+    else {
+        return;
+    };
+
+    for (mut ui_image, mut style) in &mut portrait_query {
+        match &line.portrait {
+            Some(portrait) => {
+                ui_image.texture = asset_server.load(portrait.as_str());
+                style.display = Display::Flex;
+            }
+            None => style.display = Display::None,
+        }
+    }
+
+    let is_typing = dialogue_state.shown_characters < line.text.chars().count() as f32;
+    let shown_text: String = line.text.chars().take(dialogue_state.shown_characters as usize).collect();
+    let choices_text = if is_typing {
+        String::new()
+    } else {
+        line.choices
+            .iter()
+            .filter(|choice| choice.is_available(&progress.dialogue_flags))
+            .enumerate()
+            .map(|(index, choice)| {
+                let marker = if index == dialogue_state.selected_choice { "> " } else { "  " };
+                format!("{}{}", marker, choice.text)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    for (mut text, speaker_text, dialogue_text, choices) in &mut text_query {
+        let value = if speaker_text.is_some() {
+            &line.speaker
+        } else if dialogue_text.is_some() {
+            &shown_text
+        } else if choices.is_some() {
+            &choices_text
+        } else {
+            continue;
+        };
+        if text.sections[0].value != *value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
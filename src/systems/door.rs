@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::settings::*;
+
+/// How long to wait for the destination door to spawn before giving up on it
+const DOOR_LOADING_TIMEOUT: f32 = 2.0;
+
+pub fn enter_doors(
+    player_input: PlayerInput,
+    mut door_transition: ResMut<DoorTransition>,
+    door_query: Query<(&Door, &GlobalTransform)>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+) {
+    if !player_input.just_pressed(InputAction::Up) || door_transition.is_active() {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else { return };
+    let player_translation = player_transform.translation().truncate();
+
+    for (door, door_transform) in &door_query {
+        let offset = (player_translation - door_transform.translation().truncate()).abs();
+        if offset.x > door.half_extents.x || offset.y > door.half_extents.y {
+            continue;
+        }
+
+        let Some(target_level) = door.target_level.clone() else {
+            warn!("Door has no target level or target door");
+            continue;
+        };
+
+        door_transition.target_level = Some(target_level);
+        door_transition.target_door_iid = door.target_door_iid.clone();
+        let fade_seconds = door_transition.fade_seconds;
+        door_transition.set_phase(DoorTransitionPhase::FadingOut, fade_seconds);
+        break;
+    }
+}
+
+/// Fades out, switches levels, waits for the destination door, places the player there and fades back in
+pub fn door_transition(
+    time: Res<Time>,
+    mut door_transition: ResMut<DoorTransition>,
+    mut level_selection: ResMut<LevelSelection>,
+    door_query: Query<(&EntityIid, &Door, &Transform, &Parent), Without<Player>>,
+    level_query: Query<&Transform, (Without<Door>, Without<Player>)>,
+    mut player_query: Query<(&mut Transform, &mut Velocity, &Collider), With<Player>>,
+) {
+    door_transition.timer.tick(time.delta());
+    let fade_seconds = door_transition.fade_seconds;
+
+    match door_transition.phase {
+        DoorTransitionPhase::Idle => (),
+        DoorTransitionPhase::FadingOut => {
+            if door_transition.timer.finished() {
+                if let Some(target_level) = door_transition.target_level.clone() {
+                    *level_selection = target_level;
+                }
+                door_transition.set_phase(DoorTransitionPhase::Loading, DOOR_LOADING_TIMEOUT);
+            }
+        }
+        DoorTransitionPhase::Loading => {
+            let Some(target_door_iid) = door_transition.target_door_iid.clone() else {
+                door_transition.set_phase(DoorTransitionPhase::FadingIn, fade_seconds);
+                return;
+            };
+
+            let target_door = door_query.iter().find(|(entity_iid, ..)| entity_iid.as_str() == target_door_iid);
+
+            if let Some((_, door, door_transform, parent)) = target_door {
+                // doors are children of their level, which sits at its world position
+                let Ok(level_transform) = level_query.get(parent.get()) else { return };
+                let door_translation = level_transform.translation + door_transform.translation;
+
+                if let Ok((mut player_transform, mut velocity, collider)) = player_query.get_single_mut() {
+                    let player_half_height = collider.as_cuboid().map(|cuboid| cuboid.half_extents().y).unwrap_or(0.);
+
+                    // stand the player on the same floor as the door
+                    player_transform.translation.x = door_translation.x;
+                    player_transform.translation.y = door_translation.y - door.half_extents.y + player_half_height;
+                    velocity.linvel = Vec2::ZERO;
+                }
+
+                door_transition.set_phase(DoorTransitionPhase::FadingIn, fade_seconds);
+            } else if door_transition.timer.finished() {
+                warn!("Destination door {} never spawned", target_door_iid);
+                door_transition.set_phase(DoorTransitionPhase::FadingIn, fade_seconds);
+            }
+        }
+        DoorTransitionPhase::FadingIn => {
+            if door_transition.timer.finished() {
+                door_transition.set_phase(DoorTransitionPhase::Idle, 0.0);
+            }
+        }
+    }
+}
+
+/// Whichever of doors and cutscenes wants the screen darker wins
+pub fn draw_screen_fade(
+    door_transition: Res<DoorTransition>,
+    cutscene_state: Res<CutsceneState>,
+    mut fade_query: Query<&mut BackgroundColor, With<ScreenFade>>,
+) {
+    let door_alpha = match door_transition.phase {
+        DoorTransitionPhase::Idle => 0.0,
+        DoorTransitionPhase::FadingOut => door_transition.timer.percent(),
+        DoorTransitionPhase::Loading => 1.0,
+        DoorTransitionPhase::FadingIn => door_transition.timer.percent_left(),
+    };
+    let alpha = door_alpha.max(cutscene_state.fade);
+
+    for mut background_color in &mut fade_query {
+        background_color.0.set_a(alpha);
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+
+/// Falling faster than this shakes the screen on landing
+const LANDING_SHAKE_FALL_SPEED: f32 = 700.;
+
+pub fn add_trauma(
+    mut screen_shake_events: EventReader<ScreenShakeEvent>,
+    mut screen_shake: ResMut<ScreenShake>,
+) {
+    for screen_shake_event in screen_shake_events.iter() {
+        screen_shake.add_trauma(screen_shake_event.trauma);
+    }
+}
+
+/// Takes last frame's shake back off the camera before anything else looks at it,
+/// so the camera systems only ever see and move its unshaken translation
+pub fn remove_screen_shake(
+    mut screen_shake: ResMut<ScreenShake>,
+    mut camera_query: Query<&mut Transform, With<PrimaryCamera>>,
+) {
+    let Ok(mut camera_transform) = camera_query.get_single_mut() else { return };
+
+    camera_transform.translation -= screen_shake.offset.extend(0.);
+    camera_transform.rotation = Quat::IDENTITY;
+    screen_shake.offset = Vec2::ZERO;
+}
+
+/// Offsets the camera on top of wherever `player_camera` put it this frame,
+/// remembering the offset so [`remove_screen_shake`] can take it off again
+pub fn screen_shake(
+    time: Res<Time>,
+    mut screen_shake: ResMut<ScreenShake>,
+    mut camera_query: Query<&mut Transform, With<PrimaryCamera>>,
+) {
+    // keeps shaking through hit stops, which pause gameplay time
+    let delta_seconds = time.raw_delta_seconds();
+    screen_shake.elapsed += delta_seconds;
+    let trauma_decay = screen_shake.trauma_decay;
+    screen_shake.add_trauma(-trauma_decay * delta_seconds);
+
+    let Ok(mut camera_transform) = camera_query.get_single_mut() else { return };
+
+    let shake = screen_shake.shake();
+    let t = screen_shake.elapsed * screen_shake.frequency;
+
+    // a few out of phase sine waves are noisy enough for a shake
+    let wave = |phase: f32| ((t + phase).sin() + (t * 1.7 + phase * 2.3).sin() * 0.5) / 1.5;
+
+    let offset = screen_shake.max_offset * shake * Vec2::new(wave(0.0), wave(10.0));
+    screen_shake.offset = offset;
+    camera_transform.translation += offset.extend(0.);
+    camera_transform.rotation = Quat::from_rotation_z(screen_shake.max_angle * shake * wave(20.0));
+}
+
+pub fn landing_shake(
+    mut player_events: EventReader<PlayerEvent>,
+    mut screen_shake_events: EventWriter<ScreenShakeEvent>,
+) {
+    for player_event in player_events.iter() {
+        if let PlayerEvent::Landed { impact_speed } = *player_event {
+            if impact_speed > LANDING_SHAKE_FALL_SPEED {
+                screen_shake_events.send(ScreenShakeEvent {
+                    trauma: (impact_speed / LANDING_SHAKE_FALL_SPEED - 1.).clamp(0.2, 0.6),
+                });
+            }
+        }
+    }
+}
+
+pub fn hit_stop(
+    mut time: ResMut<Time>,
+    mut hit_stop_events: EventReader<HitStopEvent>,
+    mut hit_stop: ResMut<HitStop>,
+) {
+    for hit_stop_event in hit_stop_events.iter() {
+        hit_stop.start(hit_stop_event.seconds);
+    }
+
+    if hit_stop.is_stopped {
+        if !time.is_paused() {
+            time.pause();
+        }
+
+        // gameplay time is paused, so the hit stop has to run on real time
+        hit_stop.timer.tick(time.raw_delta());
+        if hit_stop.timer.finished() {
+            hit_stop.is_stopped = false;
+            time.unpause();
+        }
+    }
+}
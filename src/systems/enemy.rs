@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::utils::translation_to_grid_coords;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+
+use std::collections::{HashMap, HashSet};
+
+/// Starts promoting any pawn that is standing on a promotion tile of its level
+pub fn pawn_promotion(
+    mut commands: Commands,
+    promotion_query: Query<(&GridCoords, &Parent), With<PromotionTile>>,
+    layer_query: Query<(&Parent, &LayerMetadata)>,
+    mut pawn_query: Query<(Entity, &Transform, &Parent, &Promotable, &mut Velocity), Without<Promoting>>,
+) {
+    // Same as the walls, promotion tiles are grouped by the level they belong to
+    let mut level_to_promotion_tiles: HashMap<Entity, (i32, HashSet<GridCoords>)> = HashMap::new();
+
+    for (&grid_coords, parent) in &promotion_query {
+        if let Ok((grandparent, layer_metadata)) = layer_query.get(parent.get()) {
+            level_to_promotion_tiles
+                .entry(grandparent.get())
+                .or_insert_with(|| (layer_metadata.grid_size, HashSet::new()))
+                .1
+                .insert(grid_coords);
+        }
+    }
+
+    for (entity, transform, parent, promotable, mut velocity) in &mut pawn_query {
+        // unlike tiles, entities are direct children of their level
+        let Some((grid_size, promotion_tiles)) = level_to_promotion_tiles.get(&parent.get()) else { continue };
+
+        let pawn_coords = translation_to_grid_coords(transform.translation.truncate(), IVec2::splat(*grid_size));
+        if promotion_tiles.contains(&pawn_coords) {
+            velocity.linvel.x = 0.0;
+            commands
+                .entity(entity)
+                .insert(Promoting::new(promote_to(promotable)));
+        }
+    }
+}
+
+fn promote_to(promotable: &Promotable) -> ChessPiece {
+    // a pawn can't stay a pawn or become a king
+    match promotable.promote_to {
+        ChessPiece::Pawn | ChessPiece::King => ChessPiece::Queen,
+        piece => piece,
+    }
+}
+
+/// Flashes and grows the pawn while it is promoting,
+/// then swaps it over to the promoted piece
+pub fn animate_promotion(
+    mut commands: Commands,
+    time: Res<Time>,
+    behaviour_registry: Res<BehaviourRegistry>,
+    mut query: Query<(Entity, &mut Promoting, &mut Sprite, &mut ChessPiece, &mut Behaviour, &EntityInstance, &Transform)>,
+) {
+    for (entity, mut promoting, mut sprite, mut chess_piece, mut behaviour, entity_instance, transform) in &mut query {
+        promoting.timer.tick(time.delta());
+
+        let start_size = chess_piece.size();
+        let end_size = promoting.promote_to.size();
+
+        if !promoting.timer.finished() {
+            let progress = promoting.timer.percent();
+            let flash = (promoting.timer.elapsed_secs() * 10.) as i32 % 2 == 0;
+
+            sprite.custom_size = Some(start_size.lerp(end_size, progress));
+            sprite.color.set_a(if flash { 1.0 } else { 0.3 });
+        } else {
+            *chess_piece = promoting.promote_to;
+
+            sprite.custom_size = Some(end_size);
+            sprite.color.set_a(1.0);
+
+            // the promoted piece keeps the overrides set on the pawn in LDtk
+            if let Some(config) = behaviour_registry.config_for(chess_piece.identifier(), entity_instance) {
+                *behaviour = Behaviour::new(config, behaviour.facing_direction.clone(), transform.translation.truncate());
+            }
+
+            commands
+                .entity(entity)
+                .remove::<(Promoting, Promotable)>()
+                .insert(Collider::cuboid(end_size.x / 2., end_size.y / 2.));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::settings::*;
+use crate::states::GameState;
+
+/// Spawns the LDtk world when starting a game. Resuming from the pause menu
+/// also enters `Playing`, so nothing is spawned if the world is still around
+pub fn spawn_world(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    world_query: Query<(), With<Handle<LdtkAsset>>>,
+) {
+    if !world_query.is_empty() {
+        return;
+    }
+
+    commands.spawn(LdtkWorldBundle {
+        ldtk_handle: game_assets.ldtk.clone(),
+        ..default()
+    });
+}
+
+/// Despawns the LDtk world with everything in it and resets whatever the last game left behind,
+/// so the next game starts from scratch
+pub fn despawn_world(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut door_transition: ResMut<DoorTransition>,
+    mut hit_stop: ResMut<HitStop>,
+    mut screen_shake: ResMut<ScreenShake>,
+    mut music_manager: ResMut<MusicManager>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    world_query: Query<Entity, With<Handle<LdtkAsset>>>,
+    looping_query: Query<Entity, With<LoopingSound>>,
+    music_query: Query<Entity, With<MusicPlayer>>,
+    backdrop_query: Query<Entity, With<Backdrop>>,
+    banner_query: Query<Entity, With<AbilityBanner>>,
+    mut camera_query: Query<(&mut Transform, &mut CameraController, &mut CameraHandoff), With<PrimaryCamera>>,
+) {
+    let leftovers = looping_query.iter().chain(&music_query).chain(&backdrop_query).chain(&banner_query);
+    for entity in world_query.iter().chain(leftovers) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    *level_selection = LevelSelection::Index(0);
+    *door_transition = DoorTransition::default();
+    *screen_shake = ScreenShake::default();
+    music_manager.level_track = None;
+    music_manager.boss_track = None;
+    music_manager.is_ducked = false;
+    rapier_config.physics_pipeline_active = true;
+
+    if hit_stop.is_stopped {
+        time.unpause();
+    }
+    *hit_stop = HitStop::default();
+
+    for (mut transform, mut camera_controller, mut camera_handoff) in &mut camera_query {
+        transform.translation.x = 0.;
+        transform.translation.y = 0.;
+        transform.rotation = Quat::IDENTITY;
+        *camera_controller = CameraController::default();
+        *camera_handoff = CameraHandoff::default();
+    }
+}
+
+/// Forgets the progress of the last game, a loaded save fills it back in
+pub fn reset_progress(
+    mut checkpoint: ResMut<Checkpoint>,
+    mut unlocked_abilities: ResMut<UnlockedAbilities>,
+    mut progress: ResMut<Progress>,
+    mut input_lock: ResMut<InputLock>,
+    mut ability_cutscene: ResMut<AbilityCutscene>,
+    mut dialogue_state: ResMut<DialogueState>,
+    mut cutscene_state: ResMut<CutsceneState>,
+    mut scripted_input: ResMut<ScriptedInput>,
+) {
+    *checkpoint = Checkpoint::default();
+    *unlocked_abilities = UnlockedAbilities::default();
+    *progress = Progress::default();
+    *input_lock = InputLock::default();
+    *ability_cutscene = AbilityCutscene::default();
+    *dialogue_state = DialogueState::default();
+    *cutscene_state = CutsceneState::default();
+    *scripted_input = ScriptedInput::default();
+}
+
+/// The pause key or start on any gamepad
+pub fn toggle_pause(
+    input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    key_rebinding: Res<KeyRebinding>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let start_pressed = gamepads
+        .iter()
+        .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start)));
+    if key_rebinding.action.is_some() || (!key_bindings.just_pressed(&input, InputAction::Pause) && !start_pressed) {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => (),
+    }
+}
+
+/// Gameplay systems and their timers only run while playing,
+/// but the physics simulation and any looping sounds have to be stopped by hand
+pub fn pause_game(
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut music_manager: ResMut<MusicManager>,
+    looping_query: Query<&AudioSink, With<LoopingSound>>,
+) {
+    rapier_config.physics_pipeline_active = false;
+    music_manager.is_ducked = true;
+    for audio_sink in &looping_query {
+        audio_sink.pause();
+    }
+}
+
+pub fn resume_game(
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut music_manager: ResMut<MusicManager>,
+    looping_query: Query<&AudioSink, With<LoopingSound>>,
+) {
+    rapier_config.physics_pipeline_active = true;
+    music_manager.is_ducked = false;
+    for audio_sink in &looping_query {
+        audio_sink.play();
+    }
+}
+
+pub fn player_death(
+    mut next_state: ResMut<NextState<GameState>>,
+    player_query: Query<&Health, With<Player>>,
+) {
+    if player_query.iter().any(|health| health.current <= 0) {
+        next_state.set(GameState::GameOver);
+    }
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+
+pub fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut query: Query<&mut Health>,
+    player_query: Query<(), With<Player>>,
+    mut hit_stop_events: EventWriter<HitStopEvent>,
+    mut screen_shake_events: EventWriter<ScreenShakeEvent>,
+    mut player_events: EventWriter<PlayerEvent>,
+) {
+    for damage_event in damage_events.iter() {
+        if let Ok(mut health) = query.get_mut(damage_event.target) {
+            if health.is_invulnerable {
+                continue;
+            }
+
+            health.current = (health.current - damage_event.amount).max(0);
+            health.is_invulnerable = true;
+            health.invulnerability_timer.reset();
+
+            if player_query.contains(damage_event.target) {
+                hit_stop_events.send(HitStopEvent { seconds: 0.1 });
+                screen_shake_events.send(ScreenShakeEvent { trauma: 0.5 });
+                player_events.send(PlayerEvent::Hurt);
+            }
+        }
+    }
+}
+
+pub fn invulnerability(
+    time: Res<Time>,
+    mut query: Query<&mut Health>,
+) {
+    for mut health in &mut query {
+        if health.is_invulnerable {
+            if !health.invulnerability_timer.finished() {
+                health.invulnerability_timer.tick(time.delta());
+            } else {
+                health.is_invulnerable = false;
+            }
+        }
+    }
+}
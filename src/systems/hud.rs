@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+use crate::systems::collectible::*;
+
+const PIP_SIZE: f32 = 12.;
+const FULL_PIP_COLOR: Color = Color::rgb(0.85, 0.2, 0.25);
+const EMPTY_PIP_COLOR: Color = Color::rgba(0.85, 0.2, 0.25, 0.25);
+const DASH_READY_COLOR: Color = Color::rgb(0.4, 0.8, 1.);
+const DASH_COOLDOWN_COLOR: Color = Color::rgb(0.4, 0.4, 0.45);
+
+/// Health and the dash in the top left, collectibles in the top right and the level name along the bottom.
+/// Everything is sized in whole pixels so it stays crisp at any window scale
+pub fn spawn_hud(
+    mut commands: Commands,
+    hud_query: Query<(), With<Hud>>,
+) {
+    // resuming from the pause menu keeps the HUD that's already there
+    if !hud_query.is_empty() {
+        return;
+    }
+
+    let text_style = TextStyle {
+        font_size: 16.,
+        color: Color::WHITE,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..default()
+                },
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            Hud,
+        ))
+        .with_children(|hud| {
+            hud
+                .spawn(NodeBundle {
+                    style: Style {
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|top| {
+                    top
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                row_gap: Val::Px(4.),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|left| {
+                            // the pips are filled in by update_health_pips
+                            left.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        column_gap: Val::Px(4.),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                HealthPips,
+                            ));
+                            left
+                                .spawn((
+                                    NodeBundle {
+                                        style: Style {
+                                            width: Val::Px(48.),
+                                            height: Val::Px(4.),
+                                            ..default()
+                                        },
+                                        background_color: Color::rgba(0., 0., 0., 0.5).into(),
+                                        ..default()
+                                    },
+                                    DashIndicator,
+                                ))
+                                .with_children(|dash_indicator| {
+                                    dash_indicator.spawn((
+                                        NodeBundle {
+                                            style: Style {
+                                                width: Val::Percent(100.),
+                                                height: Val::Percent(100.),
+                                                ..default()
+                                            },
+                                            background_color: DASH_READY_COLOR.into(),
+                                            ..default()
+                                        },
+                                        DashIndicatorFill,
+                                    ));
+                                });
+                        });
+                    top.spawn((TextBundle::from_section("", text_style.clone()), CollectibleCounter));
+                });
+
+            hud.spawn((TextBundle::from_section("", text_style), LevelNameText));
+        });
+}
+
+pub fn update_health_pips(
+    mut commands: Commands,
+    player_query: Query<&Health, With<Player>>,
+    pips_query: Query<(Entity, Option<&Children>), With<HealthPips>>,
+    mut pip_query: Query<(&HealthPip, &mut BackgroundColor)>,
+) {
+    let Ok(health) = player_query.get_single() else { return };
+    let Ok((pips_entity, children)) = pips_query.get_single() else { return };
+
+    // respawn the pips whenever the max health changes
+    let pip_count = children.map_or(0, |children| children.len());
+    if pip_count != health.max.max(0) as usize {
+        commands.entity(pips_entity).despawn_descendants().with_children(|pips| {
+            for index in 0..health.max {
+                pips.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(PIP_SIZE),
+                            height: Val::Px(PIP_SIZE),
+                            ..default()
+                        },
+                        background_color: FULL_PIP_COLOR.into(),
+                        ..default()
+                    },
+                    HealthPip { index },
+                ));
+            }
+        });
+        return;
+    }
+
+    for (health_pip, mut background_color) in &mut pip_query {
+        let color = if health_pip.index < health.current { FULL_PIP_COLOR } else { EMPTY_PIP_COLOR };
+        if background_color.0 != color {
+            background_color.0 = color;
+        }
+    }
+}
+
+/// Full while the dash is ready, empty while dashing, then fills back up over the cooldown
+pub fn update_dash_indicator(
+    unlocked_abilities: Res<UnlockedAbilities>,
+    player_query: Query<&HorizontalMover, With<Player>>,
+    mut indicator_query: Query<&mut Style, (With<DashIndicator>, Without<DashIndicatorFill>)>,
+    mut fill_query: Query<(&mut Style, &mut BackgroundColor), With<DashIndicatorFill>>,
+) {
+    let Ok(horizontal_mover) = player_query.get_single() else { return };
+
+    for mut style in &mut indicator_query {
+        style.display = if unlocked_abilities.has(Ability::Dash) { Display::Flex } else { Display::None };
+    }
+
+    let (progress, color) = if horizontal_mover.can_dash {
+        (1., DASH_READY_COLOR)
+    } else if horizontal_mover.is_dashing {
+        (0., DASH_COOLDOWN_COLOR)
+    } else {
+        (horizontal_mover.dash_cooldown_timer.percent(), DASH_COOLDOWN_COLOR)
+    };
+    for (mut style, mut background_color) in &mut fill_query {
+        style.width = Val::Percent(progress * 100.);
+        background_color.0 = color;
+    }
+}
+
+/// Tallies what has been collected by kind, looking the kinds up in the LDtk project since the save only keeps IIDs
+pub fn update_collectible_counter(
+    progress: Res<Progress>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut text_query: Query<&mut Text, With<CollectibleCounter>>,
+) {
+    let Some(ldtk_asset) = ldtk_query.get_single().ok().and_then(|ldtk_handle| ldtk_assets.get(ldtk_handle)) else { return };
+
+    let (mut dream_shards, mut chess_pieces) = (0, 0);
+    for entity_instance in ldtk_asset.iter_levels().flat_map(level_collectibles) {
+        if !progress.collected_items.contains(&entity_instance.iid) {
+            continue;
+        }
+        match CollectibleKind::from_entity_instance(entity_instance) {
+            CollectibleKind::DreamShard => dream_shards += 1,
+            CollectibleKind::ChessPiece => chess_pieces += 1,
+        }
+    }
+
+    let label = format!("Shards {}  Pieces {}", dream_shards, chess_pieces);
+    for mut text in &mut text_query {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+/// The level's identifier, along with how much of it has been collected if there is anything to collect
+pub fn update_level_name(
+    progress: Res<Progress>,
+    level_selection: Res<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut text_query: Query<&mut Text, With<LevelNameText>>,
+) {
+    let Some(level) = ldtk_query
+        .get_single()
+        .ok()
+        .and_then(|ldtk_handle| ldtk_assets.get(ldtk_handle))
+        .and_then(|ldtk_asset| ldtk_asset.get_level(&level_selection))
+    else {
+        return;
+    };
+
+    let name = level.identifier.replace('_', " ");
+    let label = match level_completion(level, &progress) {
+        (_, 0) => name,
+        (collected, total) => format!("{}  {}%", name, collected * 100 / total),
+    };
+    for mut text in &mut text_query {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
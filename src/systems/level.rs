@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+
+/// Follows the player into neighbouring levels.
+///
+/// Levels are spawned at their world position with their neighbours loaded,
+/// so walking off the edge of one level walks straight into the next.
+/// Once the player is in a neighbour, it becomes the selected level,
+/// and the player is placed just inside its matching edge so they don't flicker between the two.
+pub fn update_level_selection(
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    level_query: Query<(&Handle<LdtkLevel>, &Transform), Without<Player>>,
+    mut player_query: Query<(&mut Transform, &Collider), With<Player>>,
+    mut level_selection: ResMut<LevelSelection>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    door_transition: Res<DoorTransition>,
+) {
+    // doors take care of the level selection themselves
+    if door_transition.is_active() {
+        return;
+    }
+    let Ok((mut player_transform, collider)) = player_query.get_single_mut() else { return };
+    let Some(current_iid) = ldtk_query
+        .get_single()
+        .ok()
+        .and_then(|ldtk_handle| ldtk_assets.get(ldtk_handle))
+        .and_then(|ldtk_asset| ldtk_asset.get_level(&level_selection))
+        .map(|level| level.iid.clone())
+    else {
+        return;
+    };
+
+    let player_translation = player_transform.translation.truncate();
+    let half_extents = collider.as_cuboid().map(|cuboid| cuboid.half_extents()).unwrap_or(Vec2::ZERO);
+
+    let loaded_levels: Vec<(&ldtk::Level, Rect)> = level_query
+        .iter()
+        .filter_map(|(level_handle, level_transform)| {
+            let level = &ldtk_levels.get(level_handle)?.level;
+            let min = level_transform.translation.truncate();
+            Some((level, Rect::from_corners(min, min + Vec2::new(level.px_wid as f32, level.px_hei as f32))))
+        })
+        .collect();
+
+    let Some((current_level, current_bounds)) = loaded_levels.iter().find(|(level, _)| level.iid == current_iid) else { return };
+    if current_bounds.contains(player_translation) {
+        return;
+    }
+
+    for (level, bounds) in &loaded_levels {
+        let is_neighbour = current_level.neighbours.iter().any(|neighbour| neighbour.level_iid == level.iid);
+        if !is_neighbour || !bounds.contains(player_translation) {
+            continue;
+        }
+
+        *level_selection = LevelSelection::Iid(level.iid.clone());
+
+        player_transform.translation.x = player_translation.x.clamp(bounds.min.x + half_extents.x, bounds.max.x - half_extents.x);
+        player_transform.translation.y = player_translation.y.clamp(bounds.min.y + half_extents.y, bounds.max.y - half_extents.y);
+        break;
+    }
+}
+
+/// Saves the first bit of solid ground the player stands on in each level they enter
+pub fn update_checkpoint(
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    level_selection: Res<LevelSelection>,
+    door_transition: Res<DoorTransition>,
+    mut checkpoint: ResMut<Checkpoint>,
+    player_query: Query<(&Transform, &GroundDetection), With<Player>>,
+) {
+    if door_transition.is_active() {
+        return;
+    }
+    let Ok((player_transform, ground_detection)) = player_query.get_single() else { return };
+    if !ground_detection.on_ground {
+        return;
+    }
+    let Some(current_iid) = ldtk_query
+        .get_single()
+        .ok()
+        .and_then(|ldtk_handle| ldtk_assets.get(ldtk_handle))
+        .and_then(|ldtk_asset| ldtk_asset.get_level(&level_selection))
+        .map(|level| level.iid.clone())
+    else {
+        return;
+    };
+
+    if checkpoint.level_iid.as_ref() != Some(&current_iid) {
+        checkpoint.level = LevelSelection::Iid(current_iid.clone());
+        checkpoint.level_iid = Some(current_iid);
+        checkpoint.translation = player_transform.translation.truncate();
+    }
+}
+
+/// A loaded game starts the player at the saved checkpoint rather than wherever they are placed in LDtk
+pub fn spawn_at_checkpoint(
+    checkpoint: Res<Checkpoint>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut player_query: Query<&mut Transform, Added<Player>>,
+) {
+    if checkpoint.level_iid.is_none() {
+        return;
+    }
+    for mut transform in &mut player_query {
+        *level_selection = checkpoint.level.clone();
+        transform.translation.x = checkpoint.translation.x;
+        transform.translation.y = checkpoint.translation.y;
+    }
+}
+
+/// Puts the player back at the checkpoint with full health, the rest of the world carries on as it was
+/// Runs in every state right after the menus, so the Restart button's event can't be missed while the pause menu closes
+pub fn restart_from_checkpoint(
+    mut menu_events: EventReader<MenuEvent>,
+    checkpoint: Res<Checkpoint>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut player_query: Query<(&mut Transform, &mut Velocity, &mut Health), With<Player>>,
+) {
+    if !menu_events.iter().any(|menu_event| menu_event.action == MenuAction::Restart && menu_event.step == 0) {
+        return;
+    }
+    // no checkpoint yet, the player hasn't touched the ground since the game started
+    if checkpoint.level_iid.is_none() {
+        return;
+    }
+
+    *level_selection = checkpoint.level.clone();
+    for (mut transform, mut velocity, mut health) in &mut player_query {
+        transform.translation.x = checkpoint.translation.x;
+        transform.translation.y = checkpoint.translation.y;
+        *velocity = Velocity::zero();
+        health.current = health.max;
+        health.is_invulnerable = false;
+    }
+}
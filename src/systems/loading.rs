@@ -0,0 +1,166 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::animation::AnimationSheet;
+use crate::components::*;
+use crate::resources::*;
+use crate::states::GameState;
+use crate::systems::menu::spawn_menu;
+
+/// Starts loading everything gameplay needs and puts up the loading screen
+pub fn start_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    backdrop_registry: Res<BackdropRegistry>,
+    sound_registry: Res<SoundRegistry>,
+    music_registry: Res<MusicRegistry>,
+) {
+    let mut loading_tracker = LoadingTracker::default();
+
+    let ldtk_path = "enter_the_dreamer.ldtk";
+    let player_animations_path = "animations/player.anim.ron";
+    let game_assets = GameAssets {
+        ldtk: asset_server.load(ldtk_path),
+        player_animations: asset_server.load(player_animations_path),
+    };
+    loading_tracker.require(ldtk_path, &game_assets.ldtk);
+    loading_tracker.require(player_animations_path, &game_assets.player_animations);
+
+    let mut backdrop_images: Vec<&String> = backdrop_registry
+        .backdrops
+        .values()
+        .flatten()
+        .map(|layer| &layer.image)
+        .collect();
+    backdrop_images.sort();
+    backdrop_images.dedup();
+    for path in backdrop_images {
+        let handle: Handle<Image> = asset_server.load(path.as_str());
+        loading_tracker.require(path, &handle);
+    }
+
+    let audio_paths = sound_registry
+        .sounds
+        .values()
+        .map(|sound| &sound.path)
+        .chain(music_registry.tracks.values().map(|track| &track.path));
+    for path in audio_paths {
+        let handle: Handle<AudioSource> = asset_server.load(path.as_str());
+        loading_tracker.want(path, &handle);
+    }
+
+    commands.insert_resource(game_assets);
+    commands.insert_resource(loading_tracker);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                z_index: ZIndex::Global(50),
+                ..default()
+            },
+            LoadingScreen,
+        ))
+        .with_children(|loading_screen| {
+            loading_screen.spawn((
+                TextBundle::from_section("Loading", TextStyle {
+                    font_size: 24.,
+                    color: Color::WHITE,
+                    ..default()
+                }),
+                LoadingProgressText,
+            ));
+        });
+}
+
+/// Waits for everything to load, picking up the levels, tilesets and textures the first assets point to,
+/// then heads to the main menu. A required asset failing shows an error screen instead
+pub fn track_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_assets: Res<GameAssets>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    animation_sheets: Res<Assets<AnimationSheet>>,
+    mut loading_tracker: ResMut<LoadingTracker>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut text_query: Query<&mut Text, With<LoadingProgressText>>,
+    loading_screen_query: Query<Entity, With<LoadingScreen>>,
+) {
+    if loading_tracker.has_failed {
+        return;
+    }
+
+    if let (false, Some(ldtk_asset)) = (loading_tracker.has_ldtk_dependencies, ldtk_assets.get(&game_assets.ldtk)) {
+        for (identifier, level_handle) in &ldtk_asset.level_map {
+            loading_tracker.require(identifier, level_handle);
+        }
+        for (uid, tileset_handle) in &ldtk_asset.tileset_map {
+            loading_tracker.require(&format!("tileset {}", uid), tileset_handle);
+        }
+        loading_tracker.has_ldtk_dependencies = true;
+    }
+
+    if let (false, Some(animation_sheet)) = (loading_tracker.has_animation_dependencies, animation_sheets.get(&game_assets.player_animations)) {
+        let texture: Handle<Image> = asset_server.load(animation_sheet.texture.as_str());
+        loading_tracker.require(&animation_sheet.texture, &texture);
+        loading_tracker.has_animation_dependencies = true;
+    }
+
+    let load_state = |(path, handle): &(String, HandleUntyped)| (path.clone(), asset_server.get_load_state(handle));
+
+    let required: Vec<(String, LoadState)> = loading_tracker.required.iter().map(load_state).collect();
+    let optional: Vec<(String, LoadState)> = loading_tracker.optional.iter().map(load_state).collect();
+
+    for (path, _) in optional.iter().filter(|(_, state)| *state == LoadState::Failed) {
+        if !loading_tracker.reported_failures.contains(path) {
+            warn!("Couldn't load {}, carrying on without it", path);
+            loading_tracker.reported_failures.push(path.clone());
+        }
+    }
+
+    let failed: Vec<&String> = required
+        .iter()
+        .filter(|(_, state)| *state == LoadState::Failed)
+        .map(|(path, _)| path)
+        .collect();
+    if !failed.is_empty() {
+        for path in &failed {
+            error!("Couldn't load {}", path);
+        }
+        for entity in &loading_screen_query {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        let message = format!(
+            "These files are missing or broken:\n{}\n\nCheck the assets folder and start the game again.",
+            failed.iter().map(|path| path.as_str()).collect::<Vec<_>>().join("\n"),
+        );
+        spawn_menu(&mut commands, LoadingScreen, "Couldn't load the game", &message, &[
+            ("Quit", MenuAction::Quit),
+        ]);
+        loading_tracker.has_failed = true;
+        return;
+    }
+
+    let is_done = |state: &LoadState| matches!(state, LoadState::Loaded | LoadState::Failed);
+    let finished = required.iter().chain(&optional).filter(|(_, state)| is_done(state)).count();
+    let total = required.len() + optional.len();
+
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("Loading {}/{}", finished, total);
+    }
+
+    let dependencies_known = loading_tracker.has_ldtk_dependencies && loading_tracker.has_animation_dependencies;
+    if dependencies_known && finished == total {
+        next_state.set(GameState::MainMenu);
+    }
+}
@@ -0,0 +1,337 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::save::*;
+use crate::settings::*;
+use crate::states::GameState;
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const SELECTED_BUTTON_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+
+/// Spawns a full screen menu with a title, an optional message and a button for each action
+pub fn spawn_menu(commands: &mut Commands, screen: impl Component, title: &str, message: &str, buttons: &[(&str, MenuAction)]) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(6.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                z_index: ZIndex::Global(50),
+                ..default()
+            },
+            Menu::default(),
+            screen,
+        ))
+        .with_children(|menu| {
+            menu.spawn(TextBundle::from_section(title, TextStyle {
+                font_size: 40.,
+                color: Color::WHITE,
+                ..default()
+            }).with_style(Style {
+                margin: UiRect::bottom(Val::Px(16.)),
+                ..default()
+            }));
+
+            if !message.is_empty() {
+                menu.spawn(TextBundle::from_section(message, TextStyle {
+                    font_size: 16.,
+                    color: Color::rgb(0.8, 0.8, 0.8),
+                    ..default()
+                }).with_style(Style {
+                    max_width: Val::Percent(80.),
+                    margin: UiRect::bottom(Val::Px(16.)),
+                    ..default()
+                }));
+            }
+
+            for (index, (label, action)) in buttons.iter().enumerate() {
+                menu
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(180.),
+                                height: Val::Px(28.),
+                                align_items: AlignItems::Center,
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        MenuButton { index, action: *action },
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(*label, TextStyle {
+                            font_size: 20.,
+                            color: Color::WHITE,
+                            ..default()
+                        }));
+                    });
+            }
+        });
+}
+
+pub fn spawn_main_menu(mut commands: Commands) {
+    spawn_main_screen(&mut commands);
+}
+
+fn spawn_main_screen(commands: &mut Commands) {
+    spawn_menu(commands, MainMenuScreen, "Enter the Dreamer", "", &[
+        ("Play", MenuAction::Play),
+        ("Settings", MenuAction::Settings),
+        ("Quit", MenuAction::Quit),
+    ]);
+}
+
+pub fn spawn_game_over(mut commands: Commands) {
+    spawn_menu(&mut commands, GameOverScreen, "Game Over", "", &[
+        ("Retry", MenuAction::Retry),
+        ("Main Menu", MenuAction::MainMenu),
+    ]);
+}
+
+pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Moves through menu buttons with the arrow keys or the mouse, and picks one with Enter or a click
+pub fn menu_navigation(
+    input: Res<Input<KeyCode>>,
+    key_rebinding: Res<KeyRebinding>,
+    mut menu_query: Query<(&mut Menu, &Children)>,
+    interaction_query: Query<(&MenuButton, &Interaction), Changed<Interaction>>,
+    mut button_query: Query<(&MenuButton, &mut BackgroundColor)>,
+    mut menu_events: EventWriter<MenuEvent>,
+) {
+    if key_rebinding.action.is_some() {
+        return;
+    }
+
+    for (mut menu, children) in &mut menu_query {
+        let button_count = children.iter().filter(|child| button_query.contains(**child)).count();
+        if button_count == 0 {
+            continue;
+        }
+
+        if input.just_pressed(KeyCode::Down) {
+            menu.selected = (menu.selected + 1) % button_count;
+        }
+        if input.just_pressed(KeyCode::Up) {
+            menu.selected = (menu.selected + button_count - 1) % button_count;
+        }
+        let step = if input.just_pressed(KeyCode::Left) {
+            -1
+        } else if input.just_pressed(KeyCode::Right) {
+            1
+        } else {
+            0
+        };
+
+        // only react to the mouse when it does something, a held click shouldn't press the button every frame
+        for child in children {
+            let Ok((menu_button, interaction)) = interaction_query.get(*child) else { continue };
+            match interaction {
+                Interaction::Hovered => menu.selected = menu_button.index,
+                Interaction::Pressed => {
+                    menu.selected = menu_button.index;
+                    menu_events.send(MenuEvent { action: menu_button.action, step: 0 });
+                }
+                Interaction::None => (),
+            }
+        }
+
+        for child in children {
+            let Ok((menu_button, mut background_color)) = button_query.get_mut(*child) else { continue };
+            let is_selected = menu_button.index == menu.selected;
+
+            *background_color = if is_selected { SELECTED_BUTTON_COLOR } else { BUTTON_COLOR }.into();
+
+            if is_selected && input.just_pressed(KeyCode::Return) {
+                menu_events.send(MenuEvent { action: menu_button.action, step: 0 });
+            }
+            if is_selected && step != 0 {
+                menu_events.send(MenuEvent { action: menu_button.action, step });
+            }
+        }
+    }
+}
+
+pub fn spawn_pause_menu(mut commands: Commands) {
+    spawn_pause_screen(&mut commands);
+}
+
+fn spawn_pause_screen(commands: &mut Commands) {
+    spawn_menu(commands, PauseScreen, "Paused", "", &[
+        ("Resume", MenuAction::Resume),
+        ("Restart", MenuAction::Restart),
+        ("Settings", MenuAction::Settings),
+        ("Main Menu", MenuAction::MainMenu),
+    ]);
+}
+
+fn spawn_settings_menu(commands: &mut Commands) {
+    // the labels are filled in by update_setting_labels
+    spawn_menu(commands, SettingsScreen, "Settings", "Left and right change the setting", &[
+        ("", MenuAction::Volume(VolumeChannel::Master)),
+        ("", MenuAction::Volume(VolumeChannel::Music)),
+        ("", MenuAction::Volume(VolumeChannel::Sfx)),
+        ("", MenuAction::Display(DisplayOption::Scale)),
+        ("", MenuAction::Display(DisplayOption::Fullscreen)),
+        ("", MenuAction::Display(DisplayOption::Vsync)),
+        ("", MenuAction::Display(DisplayOption::AlwaysOnTop)),
+        ("Key Bindings", MenuAction::KeyBindings),
+        ("Back", MenuAction::Back),
+    ]);
+}
+
+fn spawn_save_slots_menu(commands: &mut Commands) {
+    let labels: Vec<String> = (0..SAVE_SLOTS)
+        .map(|slot| {
+            let summary = match SaveData::load(slot) {
+                Ok(Some(save_data)) => save_data.summary(),
+                Ok(None) => "Empty".to_string(),
+                Err(_) => "Unreadable".to_string(),
+            };
+            format!("{}: {}", slot + 1, summary)
+        })
+        .collect();
+    let buttons: Vec<_> = labels
+        .iter()
+        .enumerate()
+        .map(|(slot, label)| (label.as_str(), MenuAction::SaveSlot(slot)))
+        .chain([("Back", MenuAction::Back)])
+        .collect();
+    spawn_menu(commands, SaveSlotsScreen, "Pick a Save", "", &buttons);
+}
+
+fn spawn_key_bindings_menu(commands: &mut Commands) {
+    let buttons: Vec<_> = InputAction::ALL
+        .iter()
+        .map(|action| ("", MenuAction::Rebind(*action)))
+        .chain([("Back", MenuAction::Settings)])
+        .collect();
+    spawn_menu(commands, KeyBindingsScreen, "Key Bindings", "Pick an action, then press the key for it", &buttons);
+}
+
+/// Only picking a button does anything here, stepping left and right is for the settings
+pub fn menu_actions(
+    mut commands: Commands,
+    mut menu_events: EventReader<MenuEvent>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut key_rebinding: ResMut<KeyRebinding>,
+    mut app_exit_events: EventWriter<AppExit>,
+    screen_query: Query<Entity, Or<(With<MainMenuScreen>, With<PauseScreen>, With<SaveSlotsScreen>, With<SettingsScreen>, With<KeyBindingsScreen>)>>,
+) {
+    for menu_event in menu_events.iter().filter(|menu_event| menu_event.step == 0) {
+        match menu_event.action {
+            MenuAction::Retry | MenuAction::Resume | MenuAction::Restart => next_state.set(GameState::Playing),
+            MenuAction::Play | MenuAction::Settings | MenuAction::Back | MenuAction::KeyBindings => {
+                for entity in &screen_query {
+                    commands.entity(entity).despawn_recursive();
+                }
+                match menu_event.action {
+                    MenuAction::Play => spawn_save_slots_menu(&mut commands),
+                    MenuAction::Settings => spawn_settings_menu(&mut commands),
+                    MenuAction::KeyBindings => spawn_key_bindings_menu(&mut commands),
+                    _ if *state.get() == GameState::MainMenu => spawn_main_screen(&mut commands),
+                    _ => spawn_pause_screen(&mut commands),
+                }
+            }
+            MenuAction::Rebind(action) => key_rebinding.action = Some(action),
+            MenuAction::Volume(_) | MenuAction::Display(_) | MenuAction::SaveSlot(_) => (),
+            MenuAction::MainMenu => next_state.set(GameState::MainMenu),
+            MenuAction::Quit => app_exit_events.send(AppExit),
+        }
+    }
+}
+
+/// Steps the volumes by a tenth and the display settings through their options,
+/// picking a volume button cycles it back round to zero after full volume
+pub fn settings_actions(
+    mut menu_events: EventReader<MenuEvent>,
+    mut volume_settings: ResMut<VolumeSettings>,
+    mut display_settings: ResMut<DisplaySettings>,
+) {
+    for menu_event in menu_events.iter() {
+        match menu_event.action {
+            MenuAction::Volume(channel) => {
+                let volume = volume_settings.channel_mut(channel);
+
+                *volume = if menu_event.step == 0 {
+                    if *volume >= 1. { 0. } else { (*volume + 0.1).min(1.) }
+                } else {
+                    (*volume + 0.1 * menu_event.step as f32).clamp(0., 1.)
+                };
+            }
+            MenuAction::Display(DisplayOption::Scale) => {
+                let largest_scale = DisplaySettings::SCALES[DisplaySettings::SCALES.len() - 1];
+                if menu_event.step == 0 && display_settings.scale >= largest_scale {
+                    display_settings.scale = DisplaySettings::SCALES[0];
+                } else {
+                    display_settings.step_scale(if menu_event.step == 0 { 1 } else { menu_event.step });
+                }
+            }
+            MenuAction::Display(DisplayOption::Fullscreen) => display_settings.fullscreen = !display_settings.fullscreen,
+            MenuAction::Display(DisplayOption::Vsync) => display_settings.vsync = !display_settings.vsync,
+            MenuAction::Display(DisplayOption::AlwaysOnTop) => display_settings.always_on_top = !display_settings.always_on_top,
+            _ => (),
+        }
+    }
+}
+
+/// Writes the current value of each setting into its button
+pub fn update_setting_labels(
+    volume_settings: Res<VolumeSettings>,
+    display_settings: Res<DisplaySettings>,
+    key_bindings: Res<KeyBindings>,
+    key_rebinding: Res<KeyRebinding>,
+    button_query: Query<(&MenuButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    let on_off = |is_on: bool| if is_on { "On" } else { "Off" };
+
+    for (menu_button, children) in &button_query {
+        let label = match menu_button.action {
+            MenuAction::Volume(channel) => {
+                let name = match channel {
+                    VolumeChannel::Master => "Master",
+                    VolumeChannel::Music => "Music",
+                    VolumeChannel::Sfx => "Sound",
+                };
+                format!("< {} {}% >", name, (volume_settings.channel(channel) * 100.).round())
+            }
+            MenuAction::Display(DisplayOption::Scale) => format!("< Window {}x >", display_settings.scale),
+            MenuAction::Display(DisplayOption::Fullscreen) => format!("Fullscreen: {}", on_off(display_settings.fullscreen)),
+            MenuAction::Display(DisplayOption::Vsync) => format!("VSync: {}", on_off(display_settings.vsync)),
+            MenuAction::Display(DisplayOption::AlwaysOnTop) => format!("On Top: {}", on_off(display_settings.always_on_top)),
+            MenuAction::Rebind(action) if key_rebinding.action == Some(action) => format!("{}: ...", action.name()),
+            MenuAction::Rebind(action) => {
+                let keys: Vec<_> = key_bindings.keys(action).iter().map(|key| format!("{:?}", key)).collect();
+                format!("{}: {}", action.name(), keys.join(", "))
+            }
+            _ => continue,
+        };
+
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
@@ -1,122 +1,56 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use crate::components::*;
-
-pub fn horizontal_movement (
-    time: Res<Time>,
-    input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut Velocity, &mut HorizontalMover, &VerticalMover, &GroundDetection), With<Player>>
-) {
-    for (mut velocity, mut horizontal_mover, vertical_mover, ground_detection) in &mut query {
-        let right = if input.pressed(KeyCode::Right) && !horizontal_mover.is_dashing && !vertical_mover.is_wall_jumping { 
-            horizontal_mover.facing_direction = FacingDirection::Right;
-            1.
-        } else { 0. };
-        let left = if input.pressed(KeyCode::Left) && !horizontal_mover.is_dashing && !vertical_mover.is_wall_jumping { 
-            horizontal_mover.facing_direction = FacingDirection::Left;
-            1. 
-        } else { 0. };
-        let direction: f32 = right - left;
-        
-        let horizontal_speed: f32;
-        let horizontal_acc: f32;
-        let horizontal_dec: f32;
-        let horizontal_turn: f32;
-
-        if !ground_detection.on_ground { //if in the air
-            horizontal_speed = horizontal_mover.air_speed;
-            horizontal_acc = horizontal_mover.air_acc;
-            horizontal_dec = horizontal_mover.air_dec;
-            horizontal_turn = horizontal_mover.air_turn;
-        } else if input.pressed(KeyCode::ShiftLeft) || input.pressed(KeyCode::ShiftRight) { //if running
-            horizontal_speed = horizontal_mover.run_speed;
-            horizontal_acc = horizontal_mover.run_acc;
-            horizontal_dec = horizontal_mover.run_dec;
-            horizontal_turn = horizontal_mover.run_turn;
-        } else { //if walking
-            horizontal_speed = horizontal_mover.walk_speed;
-            horizontal_acc = horizontal_mover.walk_acc;
-            horizontal_dec = horizontal_mover.walk_dec;
-            horizontal_turn = horizontal_mover.walk_turn;
-        };
-
-        let speed_change: f32;
-        if direction != 0.0 {
-            if direction.signum() != horizontal_mover.current_speed.signum() { //if the player changes direction
-                speed_change = horizontal_turn;
-                //println!("Changing Direction");
-            } else { //if the player keeps going in the same direction
-                speed_change = horizontal_acc;
-                //println!("Same Direction");
-            }
-        } else { //slow down when the player lets go of move left and/or move right
-            speed_change = horizontal_dec;
-            //println!("Slowing Down");
-        }
-
-        if direction == 0.0 { //if decelerating
-            if horizontal_mover.current_speed > 0.0 { //decelerating from a positive velocity
-                horizontal_mover.current_speed -= speed_change;
-                if horizontal_mover.current_speed <= 0.0 {
-                    horizontal_mover.current_speed = 0.0
-                }
-            } else { //decelerating from a negative velocity
-                horizontal_mover.current_speed += speed_change;
-                if horizontal_mover.current_speed >= 0.0 {
-                    horizontal_mover.current_speed = 0.0
-                }
-            }
-        } else { //if accelerating or turning
-            horizontal_mover.current_speed += direction * speed_change;
-            if horizontal_mover.current_speed.abs() >= horizontal_speed {
-                horizontal_mover.current_speed = direction * horizontal_speed;
-            }
-        }
-
-        velocity.linvel.x = horizontal_mover.current_speed * time.delta_seconds();
-        //println!("Current Velocity: {}", velocity.linvel.x);
-    }
-}
+use crate::events::*;
+use crate::resources::*;
+use crate::settings::*;
 
 pub fn horizontal_movement_no_acc (
     time: Res<Time>,
-    input: Res<Input<KeyCode>>,
+    player_input: PlayerInput,
     mut query: Query<(&mut Velocity, &mut HorizontalMover, &VerticalMover), With<Player>>
 ) {
     for (mut velocity, mut horizontal_mover, vertical_mover) in &mut query {
-        let right = if input.pressed(KeyCode::Right) && !horizontal_mover.is_dashing && !vertical_mover.is_wall_jumping { 
+        let right = if player_input.pressed(InputAction::Right) && !horizontal_mover.is_dashing && !vertical_mover.is_wall_jumping { 
             horizontal_mover.facing_direction = FacingDirection::Right;
             1.
         } else { 0. };
-        let left = if input.pressed(KeyCode::Left) && !horizontal_mover.is_dashing && !vertical_mover.is_wall_jumping { 
+        let left = if player_input.pressed(InputAction::Left) && !horizontal_mover.is_dashing && !vertical_mover.is_wall_jumping { 
             horizontal_mover.facing_direction = FacingDirection::Left;
             1. 
         } else { 0. };
         let direction: f32 = right - left;
     
-        let horizontal_speed = if input.pressed(KeyCode::ShiftLeft) || input.pressed(KeyCode::ShiftRight) {
+        let horizontal_speed = if player_input.pressed(InputAction::Run) {
             horizontal_mover.run_speed
         } else {
         horizontal_mover.walk_speed
         };
 
+        horizontal_mover.is_horizontal_moving = direction != 0.;
+        horizontal_mover.current_speed = direction * horizontal_speed;
         velocity.linvel.x = direction * horizontal_speed * time.delta_seconds();
     }
 }
 
 pub fn horizontal_dash (
     time: Res<Time>,
-    input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut Velocity, &mut HorizontalMover), With<Player>>
+    player_input: PlayerInput,
+    unlocked_abilities: Res<UnlockedAbilities>,
+    mut screen_shake_events: EventWriter<ScreenShakeEvent>,
+    mut player_events: EventWriter<PlayerEvent>,
+    mut query: Query<(&mut Velocity, &mut HorizontalMover, &Attacker), With<Player>>
 ) {
-    for (mut velocity, mut horizontal_mover) in &mut query {
+    for (mut velocity, mut horizontal_mover, attacker) in &mut query {
         let direction = if horizontal_mover.facing_direction == FacingDirection::Left { -1. } else { 1. };
         //initiate the dash
-        if input.just_pressed(KeyCode::ControlLeft) && horizontal_mover.can_dash{
+        if player_input.just_pressed(InputAction::Dash) && unlocked_abilities.has(Ability::Dash) && horizontal_mover.can_dash && !attacker.is_attacking && !attacker.is_recovering {
             horizontal_mover.can_dash = false;
             horizontal_mover.is_dashing = true;
 
             horizontal_mover.dashing_timer.reset();
+            screen_shake_events.send(ScreenShakeEvent { trauma: 0.15 });
+            player_events.send(PlayerEvent::Dashed);
         }
         //while dashing
         if horizontal_mover.is_dashing {
@@ -163,14 +97,19 @@ pub fn set_player_gravity(
 
 pub fn vertical_jump (
     time: Res<Time>,
-    input: Res<Input<KeyCode>>,
+    player_input: PlayerInput,
+    unlocked_abilities: Res<UnlockedAbilities>,
     rapier_config: Res<RapierConfiguration>,
+    mut player_events: EventWriter<PlayerEvent>,
     mut query: Query<(&mut Velocity, &mut VerticalMover, &mut HorizontalMover, &GroundDetection, &GravityScale), With<Player>>
 ) {
     for (mut velocity, mut vertical_mover, mut horizontal_mover, ground_detection, gravity_scale) in &mut query {
-        if input.just_pressed(KeyCode::Space) && (ground_detection.on_ground || vertical_mover.jump_count > 0) {
+        //extra jumps only take effect from the next landing
+        vertical_mover.max_jump_count = unlocked_abilities.max_jump_count();
+
+        if player_input.just_pressed(InputAction::Jump) && (ground_detection.on_ground || vertical_mover.jump_count > 0) {
             vertical_mover.jump_count -= 1;
-            if input.pressed(KeyCode::ShiftLeft) || input.pressed(KeyCode::ShiftRight) {
+            if player_input.pressed(InputAction::Run) {
                 horizontal_mover.air_speed = horizontal_mover.run_speed;
             } else {
                 horizontal_mover.air_speed = horizontal_mover.walk_speed;
@@ -181,6 +120,7 @@ pub fn vertical_jump (
             println!("Gravity Scale: {}", gravity_scale.0); */
 
             velocity.linvel.y = jump_power * time.delta_seconds();
+            player_events.send(PlayerEvent::Jumped);
 
             //DEBUG
             /* vertical_mover.temp_counter += 1; */
@@ -191,13 +131,15 @@ pub fn vertical_jump (
 
 pub fn wall_jump (
     time: Res<Time>,
-    input: Res<Input<KeyCode>>,
+    player_input: PlayerInput,
+    unlocked_abilities: Res<UnlockedAbilities>,
     rapier_config: Res<RapierConfiguration>,
+    mut player_events: EventWriter<PlayerEvent>,
     mut query: Query<(&mut Velocity, &mut VerticalMover, &mut HorizontalMover, &GravityScale, &GroundDetection, &WallDetection), With<Player>>
 ) {
     for (mut velocity, mut vertical_mover, mut horizontal_mover, gravity_scale, ground_detection, wall_detection) in &mut query {
          //start the wall jump
-        if input.just_pressed(KeyCode::Space) && vertical_mover.is_wall_sliding && vertical_mover.can_wall_jump {
+        if player_input.just_pressed(InputAction::Jump) && unlocked_abilities.has(Ability::WallJump) && vertical_mover.is_wall_sliding && vertical_mover.can_wall_jump {
             vertical_mover.is_wall_sliding = false;
             vertical_mover.in_wall_slide_coyote_time = false;
             vertical_mover.can_wall_jump = false;
@@ -210,6 +152,7 @@ pub fn wall_jump (
             let jump_power = (-2. * rapier_config.gravity.y * gravity_scale.0 * vertical_mover.jump_height).sqrt();
             velocity.linvel.y = jump_power * time.delta_seconds();
             vertical_mover.jump_count = 0;
+            player_events.send(PlayerEvent::WallJumped);
             //println!("WALL JUMP");
         }
         //while wall jumping
@@ -242,8 +185,8 @@ pub fn wall_jump (
 
         //cancel wall jump early if the player hits the ground or floor
         if vertical_mover.is_wall_jumping && (ground_detection.on_ground || (wall_detection.on_wall && (
-            (input.pressed(KeyCode::Left) && horizontal_mover.facing_direction == FacingDirection::Left) || 
-            (input.pressed(KeyCode::Right) && horizontal_mover.facing_direction == FacingDirection::Right)
+            (player_input.pressed(InputAction::Left) && horizontal_mover.facing_direction == FacingDirection::Left) || 
+            (player_input.pressed(InputAction::Right) && horizontal_mover.facing_direction == FacingDirection::Right)
         ))) {
             vertical_mover.is_wall_jumping = false;
             vertical_mover.wall_jump_cooldown_timer.reset();
@@ -259,21 +202,25 @@ pub fn set_jumps(
     for (mut vertical_mover, velocity, ground_detection) in &mut query {
         if ground_detection.on_ground && velocity.linvel.y == 0.0 {
             vertical_mover.jump_count = vertical_mover.max_jump_count;
-        } else if !ground_detection.on_ground && vertical_mover.jump_count == vertical_mover.max_jump_count {
+        } else if !ground_detection.on_ground && vertical_mover.was_on_ground && vertical_mover.jump_count == vertical_mover.max_jump_count {
+            //only take away the ground jump when walking off a ledge, so jumps refreshed mid-air (pogo) are kept
             vertical_mover.jump_count -= 1;
         }
+        vertical_mover.was_on_ground = ground_detection.on_ground;
     }
 }
 
 pub fn wall_slide (
     time: Res<Time>,
-    input: Res<Input<KeyCode>>,
+    player_input: PlayerInput,
+    unlocked_abilities: Res<UnlockedAbilities>,
+    mut player_events: EventWriter<PlayerEvent>,
     mut query: Query<(&mut Velocity, &mut VerticalMover, &WallDetection, &GroundDetection, &HorizontalMover), With<Player>>
 ) {
     for (mut velocity, mut vertical_mover, wall_detection, ground_detection, horizontal_mover) in &mut query {
-        if wall_detection.on_wall && !ground_detection.on_ground && (
-            (input.pressed(KeyCode::Left) && horizontal_mover.facing_direction == FacingDirection::Left) || 
-            (input.pressed(KeyCode::Right) && horizontal_mover.facing_direction == FacingDirection::Right)
+        if unlocked_abilities.has(Ability::WallSlide) && wall_detection.on_wall && !ground_detection.on_ground && (
+            (player_input.pressed(InputAction::Left) && horizontal_mover.facing_direction == FacingDirection::Left) || 
+            (player_input.pressed(InputAction::Right) && horizontal_mover.facing_direction == FacingDirection::Right)
         ) {
             vertical_mover.is_wall_sliding = true;
             vertical_mover.in_wall_slide_coyote_time = false;
@@ -294,10 +241,18 @@ pub fn wall_slide (
             }
         }
 
+        //wall jumps end the slide too, so compare against last frame
+        if vertical_mover.is_wall_sliding && !vertical_mover.was_wall_sliding {
+            player_events.send(PlayerEvent::WallSlideStarted);
+        } else if !vertical_mover.is_wall_sliding && vertical_mover.was_wall_sliding {
+            player_events.send(PlayerEvent::WallSlideStopped);
+        }
+        vertical_mover.was_wall_sliding = vertical_mover.is_wall_sliding;
+
         if vertical_mover.is_wall_sliding {
             //println!("Wall Sliding");
-            if velocity.linvel.y < -1. * vertical_mover.wall_slide_speed {
-                velocity.linvel.y = -1. * vertical_mover.wall_slide_speed;  
+            if velocity.linvel.y < -vertical_mover.wall_slide_speed {
+                velocity.linvel.y = -vertical_mover.wall_slide_speed;  
             }
         }
     }
@@ -0,0 +1,110 @@
+use bevy::audio::{PlaybackMode, Volume};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+
+/// Levels pick their track with their `Music` field, levels without one keep the music going
+pub fn select_level_music(
+    level_selection: Res<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut music_manager: ResMut<MusicManager>,
+) {
+    let Some(level) = ldtk_query
+        .get_single()
+        .ok()
+        .and_then(|ldtk_handle| ldtk_assets.get(ldtk_handle))
+        .and_then(|ldtk_asset| ldtk_asset.get_level(&level_selection)) else { return };
+
+    if let Ok(Some(track)) = level.get_maybe_string_field("Music") {
+        if music_manager.level_track.as_ref() != Some(track) {
+            music_manager.level_track = Some(track.clone());
+        }
+    }
+}
+
+/// Bosses play their own track for as long as they are fighting the player
+pub fn boss_music(
+    mut music_manager: ResMut<MusicManager>,
+    query: Query<&Behaviour>,
+) {
+    let boss_track = query
+        .iter()
+        .filter(|behaviour| matches!(behaviour.state, BehaviourState::Chase | BehaviourState::Attack | BehaviourState::Stunned))
+        .find_map(|behaviour| behaviour.config.boss_music.clone());
+
+    if music_manager.boss_track != boss_track {
+        music_manager.boss_track = boss_track;
+    }
+}
+
+/// Fades in the track that should be playing while fading out every other one
+pub fn crossfade_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    music_registry: Res<MusicRegistry>,
+    music_manager: Res<MusicManager>,
+    volume_settings: Res<VolumeSettings>,
+    mut music_query: Query<(Entity, &mut MusicPlayer, Option<&AudioSink>)>,
+) {
+    let current_track = music_manager.current_track();
+
+    // a track that comes back while it is still fading out fades back in from where it got to
+    let mut is_playing = false;
+    for (_, mut music_player, _) in &mut music_query {
+        if Some(&music_player.name) == current_track {
+            music_player.is_fading_out = false;
+            is_playing = true;
+        }
+    }
+
+    if let (false, Some(name)) = (is_playing, current_track) {
+        // tracks that haven't been registered yet are skipped, like sound effects
+        if let Some(track) = music_registry.get(name) {
+            commands.spawn((
+                AudioBundle {
+                    source: asset_server.load(track.path.as_str()),
+                    settings: PlaybackSettings {
+                        mode: PlaybackMode::Loop,
+                        volume: Volume::new_relative(0.),
+                        ..default()
+                    },
+                },
+                MusicPlayer {
+                    name: name.clone(),
+                    volume: track.volume,
+                    fade: 0.,
+                    is_fading_out: false,
+                },
+            ));
+        }
+    }
+
+    // keeps fading through hit stops, which pause gameplay time
+    let fade_step = time.raw_delta_seconds() / music_manager.crossfade_seconds.max(f32::EPSILON);
+    let duck = if music_manager.is_ducked { music_manager.duck_volume } else { 1. };
+
+    for (entity, mut music_player, audio_sink) in &mut music_query {
+        if Some(&music_player.name) != current_track {
+            music_player.is_fading_out = true;
+        }
+
+        if music_player.is_fading_out {
+            music_player.fade = (music_player.fade - fade_step).max(0.);
+            if music_player.fade <= 0. {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        } else {
+            music_player.fade = (music_player.fade + fade_step).min(1.);
+        }
+
+        // the sink only shows up once the track has loaded
+        if let Some(audio_sink) = audio_sink {
+            audio_sink.set_volume(music_player.volume * music_player.fade * duck * volume_settings.music_volume());
+        }
+    }
+}
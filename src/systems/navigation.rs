@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::*;
+use crate::nav_graph::*;
+
+use std::collections::{HashMap, HashSet};
+
+/// Frame time the jump arc is worked out for,
+/// since the player's jump and air speed are both scaled by it
+const JUMP_ARC_FRAME_SECONDS: f32 = 1. / 60.;
+
+/// Enemies link platforms the player can jump between, so they can follow them anywhere
+fn player_jump_arc(grid_size: i32) -> JumpArc {
+    let vertical_mover = VerticalMover::default();
+    let horizontal_mover = HorizontalMover::default();
+
+    JumpArc::new(
+        vertical_mover.jump_velocity(JUMP_ARC_FRAME_SECONDS),
+        vertical_mover.jump_gravity(),
+        horizontal_mover.air_speed * JUMP_ARC_FRAME_SECONDS,
+        grid_size,
+    )
+}
+
+/// Builds the navigation graph of a level from its walls,
+/// the same way [`spawn_wall_collision`](super::setup::spawn_wall_collision) finds them.
+/// The graph lives on the level entity, so it goes away when the level unloads
+pub fn build_nav_graphs(
+    mut commands: Commands,
+    wall_query: Query<(&GridCoords, &Parent), Added<Wall>>,
+    all_walls_query: Query<(&GridCoords, &Parent), With<Wall>>,
+    parent_query: Query<&Parent, Without<Wall>>,
+    level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+    levels: Res<Assets<LdtkLevel>>,
+) {
+    if wall_query.is_empty() {
+        return;
+    }
+
+    let changed_levels: HashSet<Entity> = wall_query
+        .iter()
+        .filter_map(|(_, parent)| parent_query.get(parent.get()).ok())
+        .map(|grandparent| grandparent.get())
+        .collect();
+
+    let mut level_to_wall_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+    for (&grid_coords, parent) in &all_walls_query {
+        if let Ok(grandparent) = parent_query.get(parent.get()) {
+            if changed_levels.contains(&grandparent.get()) {
+                level_to_wall_locations
+                    .entry(grandparent.get())
+                    .or_default()
+                    .insert(grid_coords);
+            }
+        }
+    }
+
+    for (level_entity, level_handle) in &level_query {
+        let Some(level_walls) = level_to_wall_locations.get(&level_entity) else { continue };
+        let Some(walls_layer) = levels
+            .get(level_handle)
+            .and_then(|level| level.level.layer_instances.as_ref())
+            .and_then(|layers| layers.iter().find(|layer| layer.identifier == "Walls"))
+        else {
+            warn!("Level has walls but no Walls layer, skipping its navigation graph");
+            continue;
+        };
+
+        commands.entity(level_entity).insert(NavGraph::build(
+            level_walls,
+            walls_layer.c_wid,
+            walls_layer.c_hei,
+            walls_layer.grid_size,
+            player_jump_arc(walls_layer.grid_size),
+        ));
+    }
+}
+
+/// Keeps a path to the player for every chasing enemy, replanning every so often
+pub fn plan_paths(
+    time: Res<Time>,
+    level_query: Query<(&NavGraph, &GlobalTransform)>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut query: Query<(&mut Navigator, &Behaviour, &Transform, &Parent)>,
+) {
+    let Ok(player_transform) = player_query.get_single() else { return };
+
+    for (mut navigator, behaviour, transform, parent) in &mut query {
+        let Ok((nav_graph, level_transform)) = level_query.get(parent.get()) else { continue };
+
+        if behaviour.state != BehaviourState::Chase {
+            navigator.path.clear();
+            navigator.waypoint = None;
+            continue;
+        }
+
+        let translation = transform.translation.truncate();
+
+        navigator.repath_timer.tick(time.delta());
+        if navigator.repath_timer.just_finished() {
+            // enemies are children of the level, the player is a child of the world
+            let player_translation = player_transform.translation().truncate() - level_transform.translation().truncate();
+            navigator.path = nav_graph.find_path_between(translation, player_translation).unwrap_or_default();
+        }
+
+        // drop the links that have already been followed
+        if let Some(current) = nav_graph.node_below(translation) {
+            if let Some(reached) = navigator.path.iter().position(|link| link.to == current) {
+                navigator.path.drain(..=reached);
+            }
+        }
+
+        navigator.waypoint = navigator.path.first().map(|link| nav_graph.node_translation(link.to));
+    }
+}
+
+/// Jumps when the next link of the path is a jump and the enemy is on the ground
+pub fn follow_jump_links(
+    rapier_config: Res<RapierConfiguration>,
+    level_query: Query<&NavGraph>,
+    mut query: Query<(&Navigator, &Transform, &Parent, &GravityScale, &GroundDetection, &mut Velocity)>,
+) {
+    for (navigator, transform, parent, gravity_scale, ground_detection, mut velocity) in &mut query {
+        let Some(link) = navigator.path.first() else { continue };
+        if link.kind != NavLinkKind::Jump || !ground_detection.on_ground {
+            continue;
+        }
+        let Ok(nav_graph) = level_query.get(parent.get()) else { continue };
+
+        // rise to half a tile above the landing node
+        let target_y = nav_graph.node_translation(link.to).y + nav_graph.grid_size as f32 / 2.;
+        let height = (target_y - transform.translation.y).max(nav_graph.grid_size as f32);
+
+        velocity.linvel.y = (-2. * rapier_config.gravity.y * gravity_scale.0 * height).sqrt();
+    }
+}
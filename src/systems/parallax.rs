@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::components::*;
+use crate::resources::*;
+
+/// Background layers sit behind everything in the level
+const BACKDROP_Z: f32 = -100.;
+
+/// Swaps the backdrop whenever the selected level asks for a different one
+pub fn spawn_backdrop(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    backdrop_registry: Res<BackdropRegistry>,
+    level_selection: Res<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    backdrop_query: Query<(Entity, &Backdrop)>,
+    mut missing_backdrop: Local<Option<String>>,
+) {
+    let Some(level) = ldtk_query
+        .get_single()
+        .ok()
+        .and_then(|ldtk_handle| ldtk_assets.get(ldtk_handle))
+        .and_then(|ldtk_asset| ldtk_asset.get_level(&level_selection)) else { return };
+
+    let backdrop_name = match level.get_maybe_string_field("Backdrop") {
+        Ok(Some(name)) => name.clone(),
+        _ => backdrop_registry.default_backdrop.clone(),
+    };
+    // the backdrop is looked up from the entity rather than remembered, so it comes back after the world is despawned
+    let is_current = backdrop_query.iter().any(|(_, backdrop)| backdrop.name == backdrop_name);
+    if is_current || missing_backdrop.as_ref() == Some(&backdrop_name) {
+        return;
+    }
+
+    for (backdrop_entity, _) in &backdrop_query {
+        commands.entity(backdrop_entity).despawn_recursive();
+    }
+
+    if let Some(layers) = backdrop_registry.get(&backdrop_name) {
+        commands
+            .spawn((SpatialBundle::default(), Backdrop { name: backdrop_name.clone() }))
+            .with_children(|backdrop| {
+                for (index, config) in layers.iter().enumerate() {
+                    backdrop.spawn((
+                        SpatialBundle::from_transform(Transform::from_xyz(0., 0., BACKDROP_Z + index as f32)),
+                        ParallaxLayer {
+                            config: config.clone(),
+                            image: asset_server.load(config.image.as_str()),
+                            tile_size: None,
+                            tiled_area: Vec2::ZERO,
+                        },
+                    ));
+                }
+            });
+        *missing_backdrop = None;
+    } else {
+        warn!("Level {} uses unknown backdrop {}", level.identifier, backdrop_name);
+        *missing_backdrop = Some(backdrop_name);
+    }
+}
+
+/// Covers the camera's view with tiles of each layer's image,
+/// tiling again whenever the view grows past what is covered
+pub fn tile_parallax_layers(
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    camera_query: Query<&OrthographicProjection, With<PrimaryCamera>>,
+    mut layer_query: Query<(Entity, &mut ParallaxLayer)>,
+) {
+    let Ok(orthographic_projection) = camera_query.get_single() else { return };
+    let view = orthographic_projection.area.size();
+
+    for (layer_entity, mut layer) in &mut layer_query {
+        if layer.tile_size.is_some() && layer.tiled_area.cmpge(view).all() {
+            continue;
+        }
+        let Some(image) = images.get(&layer.image) else { continue };
+
+        let tile_size = image.size() * layer.config.scale;
+        // some room to spare for zooming out
+        let tiled_area = view * 1.5;
+
+        let columns = if layer.config.tile_x { (tiled_area.x / tile_size.x).ceil() as i32 + 2 } else { 1 };
+        let rows = if layer.config.tile_y { (tiled_area.y / tile_size.y).ceil() as i32 + 2 } else { 1 };
+        let first_tile = -Vec2::new((columns - 1) as f32, (rows - 1) as f32) * tile_size / 2.;
+
+        commands
+            .entity(layer_entity)
+            .despawn_descendants()
+            .with_children(|layer_parent| {
+                for column in 0..columns {
+                    for row in 0..rows {
+                        let tile_translation = first_tile + Vec2::new(column as f32, row as f32) * tile_size;
+                        layer_parent.spawn(SpriteBundle {
+                            texture: layer.image.clone(),
+                            sprite: Sprite {
+                                color: layer.config.color,
+                                custom_size: Some(tile_size),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(tile_translation.extend(0.)),
+                            ..default()
+                        });
+                    }
+                }
+            });
+
+        layer.tile_size = Some(tile_size);
+        layer.tiled_area = tiled_area;
+    }
+}
+
+/// Moves each layer by its scroll factor, wrapping tiled layers around the camera so they never run out
+pub fn scroll_parallax_layers(
+    camera_query: Query<&Transform, (With<PrimaryCamera>, Without<ParallaxLayer>)>,
+    mut layer_query: Query<(&ParallaxLayer, &mut Transform)>,
+) {
+    let Ok(camera_transform) = camera_query.get_single() else { return };
+    let camera = camera_transform.translation.truncate();
+
+    for (layer, mut transform) in &mut layer_query {
+        let Some(tile_size) = layer.tile_size else { continue };
+        let config = &layer.config;
+
+        let mut position = camera * (Vec2::ONE - config.scroll_factor) + config.offset;
+        if config.tile_x {
+            position.x = camera.x - (camera.x - position.x).rem_euclid(tile_size.x);
+        }
+        if config.lock_vertical {
+            position.y = camera.y + config.offset.y;
+        } else if config.tile_y {
+            position.y = camera.y - (camera.y - position.y).rem_euclid(tile_size.y);
+        }
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use crate::save::*;
+use crate::states::GameState;
+
+/// Starts the game in the picked slot, unless its save can't be read, which would get overwritten
+pub fn pick_save_slot(
+    mut menu_events: EventReader<MenuEvent>,
+    mut active_save: ResMut<ActiveSave>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for menu_event in menu_events.iter().filter(|menu_event| menu_event.step == 0) {
+        let MenuAction::SaveSlot(slot) = menu_event.action else { continue };
+        match SaveData::load(slot) {
+            Ok(_) => {
+                active_save.slot = Some(slot);
+                next_state.set(GameState::Playing);
+            }
+            Err(error) => error!("Couldn't load save slot {}: {}", slot + 1, error),
+        }
+    }
+}
+
+/// Restores the progress from the active save when a game starts, resuming from the pause menu leaves it alone
+pub fn restore_save(
+    active_save: Res<ActiveSave>,
+    mut checkpoint: ResMut<Checkpoint>,
+    mut unlocked_abilities: ResMut<UnlockedAbilities>,
+    mut progress: ResMut<Progress>,
+    world_query: Query<(), With<Handle<LdtkAsset>>>,
+) {
+    if !world_query.is_empty() {
+        return;
+    }
+    let Some(slot) = active_save.slot else { return };
+    let save_data = match SaveData::load(slot) {
+        Ok(Some(save_data)) => save_data,
+        Ok(None) => return,
+        Err(error) => {
+            error!("Couldn't load save slot {}: {}", slot + 1, error);
+            return;
+        }
+    };
+
+    if let Some(saved_checkpoint) = save_data.checkpoint {
+        checkpoint.level = LevelSelection::Iid(saved_checkpoint.level_iid.clone());
+        checkpoint.level_iid = Some(saved_checkpoint.level_iid);
+        checkpoint.translation = saved_checkpoint.translation;
+    }
+    unlocked_abilities.abilities = save_data.unlocked_abilities;
+    *progress = Progress {
+        collected_items: save_data.collected_items,
+        defeated_bosses: save_data.defeated_bosses,
+        dialogue_flags: save_data.dialogue_flags,
+        played_cutscenes: save_data.played_cutscenes,
+        play_time: save_data.play_time,
+    };
+}
+
+/// Writes the current progress to a save slot
+fn write_save(
+    slot: usize,
+    checkpoint: &Checkpoint,
+    unlocked_abilities: &UnlockedAbilities,
+    progress: &Progress,
+    level_selection: &LevelSelection,
+    ldtk_query: &Query<&Handle<LdtkAsset>>,
+    ldtk_assets: &Assets<LdtkAsset>,
+) {
+    let level = ldtk_query
+        .get_single()
+        .ok()
+        .and_then(|ldtk_handle| ldtk_assets.get(ldtk_handle))
+        .and_then(|ldtk_asset| ldtk_asset.get_level(level_selection));
+
+    let save_data = SaveData {
+        version: SAVE_VERSION,
+        level_name: level.map(|level| level.identifier.clone()).unwrap_or_default(),
+        checkpoint: checkpoint.level_iid.clone().map(|level_iid| SavedCheckpoint {
+            level_iid,
+            translation: checkpoint.translation,
+        }),
+        unlocked_abilities: unlocked_abilities.abilities.clone(),
+        collected_items: progress.collected_items.clone(),
+        defeated_bosses: progress.defeated_bosses.clone(),
+        dialogue_flags: progress.dialogue_flags.clone(),
+        played_cutscenes: progress.played_cutscenes.clone(),
+        play_time: progress.play_time,
+    };
+    if let Err(error) = save_data.save(slot) {
+        error!("Couldn't save to slot {}: {}", slot + 1, error);
+    }
+}
+
+/// Saves whenever the player reaches a new checkpoint, unlocks an ability or finishes a conversation
+pub fn autosave(
+    state: Res<State<GameState>>,
+    active_save: Res<ActiveSave>,
+    checkpoint: Res<Checkpoint>,
+    unlocked_abilities: Res<UnlockedAbilities>,
+    progress: Res<Progress>,
+    dialogue_state: Res<DialogueState>,
+    mut player_events: EventReader<PlayerEvent>,
+    level_selection: Res<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    // progress changes every frame with the play time, so collecting is told apart by its event
+    let is_collected = player_events.iter().any(|player_event| *player_event == PlayerEvent::Collected);
+    // runs in every state so the changes made by menus and loading a save are seen, and skipped, as they happen
+    if *state.get() != GameState::Playing {
+        return;
+    }
+    let is_new_checkpoint = checkpoint.is_changed() && !checkpoint.is_added();
+    let is_new_ability = unlocked_abilities.is_changed() && !unlocked_abilities.is_added();
+    let is_dialogue_over = dialogue_state.is_changed() && !dialogue_state.is_added() && !dialogue_state.is_active();
+    if !is_new_checkpoint && !is_new_ability && !is_dialogue_over && !is_collected {
+        return;
+    }
+    let Some(slot) = active_save.slot else { return };
+
+    write_save(slot, &checkpoint, &unlocked_abilities, &progress, &level_selection, &ldtk_query, &ldtk_assets);
+}
+
+/// Saves when the player quits to the main menu from the pause menu,
+/// runs on the transition itself so it always comes before the main menu resets the progress
+pub fn save_on_quit(
+    active_save: Res<ActiveSave>,
+    checkpoint: Res<Checkpoint>,
+    unlocked_abilities: Res<UnlockedAbilities>,
+    progress: Res<Progress>,
+    level_selection: Res<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    let Some(slot) = active_save.slot else { return };
+
+    write_save(slot, &checkpoint, &unlocked_abilities, &progress, &level_selection, &ldtk_query, &ldtk_assets);
+}
+
+pub fn track_play_time(
+    time: Res<Time>,
+    mut progress: ResMut<Progress>,
+) {
+    progress.play_time += time.delta_seconds_f64();
+}
+
+pub fn record_defeated_bosses(
+    mut progress: ResMut<Progress>,
+    query: Query<(&EntityInstance, &Behaviour), Changed<Behaviour>>,
+) {
+    for (entity_instance, behaviour) in &query {
+        if behaviour.config.is_boss && behaviour.state == BehaviourState::Dead && !progress.defeated_bosses.contains(&entity_instance.iid) {
+            progress.defeated_bosses.insert(entity_instance.iid.clone());
+        }
+    }
+}
+
+/// Bosses the player has already beaten don't come back when their level is loaded again
+pub fn despawn_defeated_bosses(
+    mut commands: Commands,
+    progress: Res<Progress>,
+    query: Query<(Entity, &EntityInstance, &Behaviour), Added<Behaviour>>,
+) {
+    for (entity, entity_instance, behaviour) in &query {
+        if behaviour.config.is_boss && progress.defeated_bosses.contains(&entity_instance.iid) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::resources::*;
+use crate::settings::*;
+
+/// Resizes and reconfigures the window whenever the display settings change,
+/// and scales the UI with it so the menus and HUD keep their size relative to the view
+pub fn apply_display_settings(
+    display_settings: Res<DisplaySettings>,
+    mut ui_scale: ResMut<UiScale>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !display_settings.is_changed() {
+        return;
+    }
+    ui_scale.scale = display_settings.scale as f64;
+
+    // the window was already created with the settings from the file
+    if display_settings.is_added() {
+        return;
+    }
+    for mut window in &mut window_query {
+        display_settings.apply(&mut window);
+    }
+}
+
+/// Writes the settings file whenever anything in it changes
+pub fn save_settings(
+    display_settings: Res<DisplaySettings>,
+    volume_settings: Res<VolumeSettings>,
+    key_bindings: Res<KeyBindings>,
+) {
+    // they are all inserted at startup, straight from the settings file
+    let is_added = display_settings.is_added() || volume_settings.is_added() || key_bindings.is_added();
+    let is_changed = display_settings.is_changed() || volume_settings.is_changed() || key_bindings.is_changed();
+    if is_added || !is_changed {
+        return;
+    }
+
+    let settings = Settings {
+        display: display_settings.clone(),
+        volume: volume_settings.clone(),
+        key_bindings: key_bindings.clone(),
+    };
+    if let Err(error) = settings.save() {
+        error!("Couldn't save the settings: {}", error);
+    }
+}
+
+/// Binds the next key pressed to the action picked on the key bindings screen
+pub fn capture_key_binding(
+    input: Res<Input<KeyCode>>,
+    mut key_rebinding: ResMut<KeyRebinding>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    // skip the frame the action was picked in, or the key that picked it would get bound
+    if key_rebinding.is_changed() {
+        return;
+    }
+    let Some(action) = key_rebinding.action else { return };
+    let Some(key) = input.get_just_pressed().next() else { return };
+
+    key_bindings.bind(action, *key);
+    key_rebinding.action = None;
+}
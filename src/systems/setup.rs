@@ -6,13 +6,23 @@ use crate::components::*;
 
 use std::collections::{HashMap, HashSet};
 
-pub fn basic_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn basic_setup(mut commands: Commands) {
     commands.spawn(PrimaryCameraBundle::default());
 
-    commands.spawn(LdtkWorldBundle {
-        ldtk_handle: asset_server.load("enter_the_dreamer.ldtk"),
-        ..default()
-    });
+    //covers the screen during door transitions
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.).into(),
+            z_index: ZIndex::Global(100),
+            ..default()
+        })
+        .insert(ScreenFade);
 }
 
 /// Spawns Rapier2d collisions for the walls of a level
@@ -78,20 +88,26 @@ pub fn spawn_wall_collision(
     if !wall_query.is_empty() {
         level_query.for_each(|(level_entity, level_handle)| {
             if let Some(level_walls) = level_to_wall_locations.get(&level_entity) {
-                let level = levels
-                    .get(level_handle)
-                    .expect("Level should be loaded by this point");
+                // the loading screen waits for every level, so this only happens with a broken project
+                let Some(level) = levels.get(level_handle) else {
+                    error!("Level has walls but its asset isn't loaded, skipping its collisions");
+                    return;
+                };
 
-                let LayerInstance {
+                let Some(LayerInstance {
                     c_wid: width,
                     c_hei: height,
                     grid_size,
                     ..
-                } = level
+                }) = level
                     .level
                     .layer_instances
-                    .clone()
-                    .expect("Level asset should have layers")[0];
+                    .as_ref()
+                    .and_then(|layer_instances| layer_instances.first())
+                    .cloned() else {
+                    error!("Level {} has no layers, skipping its collisions", level.level.identifier);
+                    return;
+                };
 
                 // combine wall tiles into flat "plates" in each individual row
                 let mut plate_stack: Vec<Vec<Plate>> = Vec::new();